@@ -0,0 +1,167 @@
+//! Warning and critical thresholds of sensor readings.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    /// Headline of the alert frame.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+/// `warn_k`/`crit_k` of one sensor. Readings at or above a threshold raise it, unless
+/// `crit` is below `warn`: then lower is worse, e.g. for a fan speed or free space.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Thresholds {
+    pub warn: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl Thresholds {
+    pub fn is_empty(&self) -> bool {
+        self.warn.is_none() && self.crit.is_none()
+    }
+
+    pub fn falling(&self) -> bool {
+        matches!((self.warn, self.crit), (Some(warn), Some(crit)) if crit < warn)
+    }
+
+    /// Whether `value` is past `threshold` in the direction of the alert.
+    pub fn crossed(&self, value: f64, threshold: f64) -> bool {
+        if self.falling() {
+            value <= threshold
+        } else {
+            value >= threshold
+        }
+    }
+
+    pub fn severity(&self, value: f64) -> Option<Severity> {
+        if value.is_nan() {
+            return None;
+        }
+        if self.crit.is_some_and(|crit| self.crossed(value, crit)) {
+            Some(Severity::Critical)
+        } else if self.warn.is_some_and(|warn| self.crossed(value, warn)) {
+            Some(Severity::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// A sensor past one of its thresholds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub severity: Severity,
+    /// Label, value and unit as shown on the alert frame.
+    pub text: String,
+}
+
+/// Lines of the alert frame: the worst severity, then the alerts from the worst down,
+/// as many as fit on `lines` lines.
+pub fn alert_lines(alerts: &[Alert], lines: usize) -> Vec<String> {
+    let mut sorted = alerts.to_vec();
+    sorted.sort_by_key(|alert| std::cmp::Reverse(alert.severity));
+    let mut alert_lines = match sorted.first() {
+        Some(worst) => vec![worst.severity.name().to_string()],
+        None => return Vec::new(),
+    };
+    alert_lines.extend(
+        sorted
+            .into_iter()
+            .take(lines.saturating_sub(1))
+            .map(|alert| alert.text),
+    );
+    alert_lines
+}
+
+/// `[Alerts]` settings shared by every sensor. `delay` and `cooldown` are in ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AlertSettings {
+    /// How far back past a threshold a reading has to go before its alert clears.
+    pub hysteresis: f64,
+    /// Ticks a reading has to stay past a threshold before the alert is raised.
+    pub delay: usize,
+    /// Ticks after an alert clears during which it isn't raised again.
+    pub cooldown: usize,
+}
+
+/// Alert of one sensor over time, updated once per tick.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlertState {
+    active: Option<Severity>,
+    acknowledged: Option<Severity>,
+    /// Ticks the reading has been past a threshold above `active`.
+    pending: usize,
+    cooldown: usize,
+}
+
+impl AlertState {
+    pub fn update(&mut self, thresholds: &Thresholds, value: f64, settings: &AlertSettings) {
+        self.cooldown = self.cooldown.saturating_sub(1);
+        let level = self.level(thresholds, value, settings.hysteresis);
+        if level <= self.active {
+            if level.is_none() && self.active.is_some() {
+                self.cooldown = settings.cooldown;
+                self.acknowledged = None;
+            }
+            self.active = level;
+            self.pending = 0;
+        } else if self.active.is_none() && self.cooldown > 0 {
+            self.pending = 0;
+        } else {
+            self.pending += 1;
+            if self.pending > settings.delay {
+                self.active = level;
+                self.pending = 0;
+            }
+        }
+    }
+
+    /// Severity to show, `None` while the alert is acknowledged. A worse severity shows
+    /// again.
+    pub fn shown(&self) -> Option<Severity> {
+        match self.acknowledged {
+            Some(acknowledged) if self.active <= Some(acknowledged) => None,
+            _ => self.active,
+        }
+    }
+
+    /// Silences the alert until it clears or gets worse.
+    pub fn acknowledge(&mut self) {
+        self.acknowledged = self.active;
+    }
+
+    /// Severity of `value`, holding on to the active one until the reading is
+    /// `hysteresis` back past its threshold.
+    fn level(&self, thresholds: &Thresholds, value: f64, hysteresis: f64) -> Option<Severity> {
+        let level = thresholds.severity(value);
+        if value.is_nan() {
+            return level;
+        }
+        let held = |severity: Severity| {
+            let threshold = match severity {
+                Severity::Warning => thresholds.warn,
+                Severity::Critical => thresholds.crit,
+            };
+            let back = if thresholds.falling() {
+                value - hysteresis
+            } else {
+                value + hysteresis
+            };
+            threshold.is_some_and(|threshold| thresholds.crossed(back, threshold))
+        };
+        [Severity::Critical, Severity::Warning]
+            .into_iter()
+            .filter(|severity| Some(*severity) <= self.active && Some(*severity) > level)
+            .find(|severity| held(*severity))
+            .or(level)
+    }
+}
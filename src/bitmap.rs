@@ -0,0 +1,237 @@
+use crate::font::{glyph, ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::sparkline::{SampleBuffer, SparklineStyle};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitmapIcon {
+    Cpu,
+    Gpu,
+    Ram,
+    Temperature,
+    Fan,
+    Clock,
+    Power,
+}
+
+impl BitmapIcon {
+    pub fn from_name(name: &str) -> Option<BitmapIcon> {
+        match name.to_lowercase().as_str() {
+            "cpu" => Some(BitmapIcon::Cpu),
+            "gpu" => Some(BitmapIcon::Gpu),
+            "ram" | "mem" => Some(BitmapIcon::Ram),
+            "temp" | "temperature" => Some(BitmapIcon::Temperature),
+            "fan" => Some(BitmapIcon::Fan),
+            "clock" => Some(BitmapIcon::Clock),
+            "power" => Some(BitmapIcon::Power),
+            _ => None,
+        }
+    }
+
+    fn rows(&self) -> [&'static str; ICON_SIZE] {
+        match self {
+            BitmapIcon::Cpu => [
+                ".#.#.#.", "#######", "##...##", "##.#.##", "##...##", "#######", ".#.#.#.",
+            ],
+            BitmapIcon::Gpu => [
+                "#######", "#.....#", "#.###.#", "#.#.#.#", "#.###.#", "#.....#", "#######",
+            ],
+            BitmapIcon::Ram => [
+                ".......", "#######", "#.#.#.#", "#.#.#.#", "#######", "#.#.#.#", ".......",
+            ],
+            BitmapIcon::Temperature => [
+                "..#....", ".#.#...", ".#.#...", ".###...", "#####..", "#####..", ".###...",
+            ],
+            BitmapIcon::Fan => [
+                "..##...", "...#...", "#..#.##", "###.###", "##.#..#", "...#...", "...##..",
+            ],
+            BitmapIcon::Clock => [
+                "..###..", ".#.#.#.", "#..#..#", "#..##.#", "#.....#", ".#...#.", "..###..",
+            ],
+            BitmapIcon::Power => [
+                "...##..", "..##...", ".##....", "######.", "...##..", "..##...", ".##....",
+            ],
+        }
+    }
+}
+
+pub const ICON_SIZE: usize = 7;
+
+/// 1-bit framebuffer for the GameSense `image-data-WxH` frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    /// Out of bounds pixels are clipped.
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = on;
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, on: bool) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.set(px, py, on);
+            }
+        }
+    }
+
+    pub fn invert(&mut self) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = !*pixel;
+        }
+    }
+
+    /// Draws `text` with its top left corner at `x`, `y` and returns the x after the last glyph.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str) -> usize {
+        let mut cursor = x;
+        for c in text.chars() {
+            let columns = glyph(c);
+            for (column, bits) in columns.iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        self.set(cursor + column, y + row, true);
+                    }
+                }
+            }
+            cursor += ADVANCE;
+        }
+        cursor
+    }
+
+    /// Width in pixels `draw_text` would use, without the trailing gap.
+    pub fn text_width(text: &str) -> usize {
+        match text.chars().count() {
+            0 => 0,
+            n => n * ADVANCE - (ADVANCE - GLYPH_WIDTH),
+        }
+    }
+
+    /// Outlined bar filled to `fraction` (clamped to 0..=1).
+    pub fn draw_bar(&mut self, x: usize, y: usize, width: usize, height: usize, fraction: f64) {
+        if width < 2 || height < 2 {
+            return;
+        }
+        for px in x..x + width {
+            self.set(px, y, true);
+            self.set(px, y + height - 1, true);
+        }
+        for py in y..y + height {
+            self.set(x, py, true);
+            self.set(x + width - 1, py, true);
+        }
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        let filled = ((width - 2) as f64 * fraction).round() as usize;
+        self.fill_rect(x + 1, y + 1, filled, height - 2, true);
+    }
+
+    /// Plots the newest samples right-aligned in the box, one column per sample, scaled to
+    /// `min..max`. Older samples that don't fit are left out.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_sparkline(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        samples: &SampleBuffer,
+        (min, max): (f64, f64),
+        style: SparklineStyle,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let bottom = y + height - 1;
+        let shown = samples.len().min(width);
+        let start = x + width - shown;
+        let mut previous: Option<usize> = None;
+        for (column, sample) in samples.samples().skip(samples.len() - shown).enumerate() {
+            if sample.is_nan() {
+                previous = None;
+                continue;
+            }
+            let fraction = if max > min {
+                ((sample - min) / (max - min)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let top = bottom - ((height - 1) as f64 * fraction).round() as usize;
+            let px = start + column;
+            match style {
+                SparklineStyle::Bars => {
+                    for py in top..=bottom {
+                        self.set(px, py, true);
+                    }
+                }
+                SparklineStyle::Line => {
+                    // Join to the previous point so steep changes stay connected
+                    let (from, to) = match previous {
+                        Some(previous) => (previous.min(top), previous.max(top)),
+                        None => (top, top),
+                    };
+                    for py in from..=to {
+                        self.set(px, py, true);
+                    }
+                }
+            }
+            previous = Some(top);
+        }
+    }
+
+    pub fn draw_icon(&mut self, x: usize, y: usize, icon: BitmapIcon) -> usize {
+        for (row, line) in icon.rows().iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if c == '#' {
+                    self.set(x + column, y + row, true);
+                }
+            }
+        }
+        x + ICON_SIZE + 1
+    }
+
+    /// Row-major bytes, 8 horizontal pixels per byte with the leftmost in the high bit, as
+    /// expected by `image-data-WxH`.
+    pub fn to_image_data(&self) -> Vec<u8> {
+        let bytes_per_row = self.width.div_ceil(8);
+        let mut data = vec![0u8; bytes_per_row * self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y) {
+                    data[y * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        data
+    }
+
+    /// `#`/`.` rendering, one line per row. Used for golden tests and debugging.
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                ascii.push(if self.get(x, y) { '#' } else { '.' });
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+pub struct GameMetadata {
+    pub game: String,
+    pub game_display_name: String,
+    pub developer: String,
+    pub icon_color_id: Option<u8>,
+    pub deinitialize_timer_length_ms: Option<u16>,
+}
+
+impl GameMetadata {
+    /// Checks the values against the limits of the GameSense `game_metadata` endpoint.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.game.is_empty()
+            || !self
+                .game
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid game name \"{}\": only A-Z, 0-9, hyphen and underscore are allowed",
+                self.game
+            );
+        }
+        if let Some(icon_color_id) = self.icon_color_id {
+            if icon_color_id > 11 {
+                bail!(
+                    "icon_color_id must be between 0 and 11, got {}",
+                    icon_color_id
+                );
+            }
+        }
+        if let Some(timer) = self.deinitialize_timer_length_ms {
+            if !(1000..=60000).contains(&timer) {
+                bail!(
+                    "deinitialize_timer_length_ms must be between 1000 and 60000, got {}",
+                    timer
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Location of SteelSeries GG's `coreProps.json`, which holds the address of the local API.
+pub fn core_props_path() -> Result<PathBuf, anyhow::Error> {
+    match std::env::consts::OS {
+        "windows" => Ok(PathBuf::from(std::env::var("PROGRAMDATA")?)
+            .join("SteelSeries")
+            .join("SteelSeries Engine 3")
+            .join("coreProps.json")),
+        "macos" => Ok(PathBuf::from(
+            "/Library/Application Support/SteelSeries Engine 3/coreProps.json",
+        )),
+        os => bail!("SteelSeries GG is not available on {}", os),
+    }
+}
+
+pub fn read_core_props(path: &Path) -> Result<String, anyhow::Error> {
+    let core_props: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    match core_props["address"].as_str() {
+        Some(address) => Ok(address.to_string()),
+        None => bail!("`address` not found in {}", path.display()),
+    }
+}
+
+/// GameSense client that survives SteelSeries GG restarts.
+///
+/// Event bindings are remembered, so when GG can't be reached the client re-reads
+/// `coreProps.json` (GG picks a new port on every start), registers the game again and
+/// replays the bindings. Reconnects back off from 1 to 30 seconds while GG is down, and
+/// frames sent in the meantime are dropped.
+pub struct SteelSeriesClient {
+    http: reqwest::blocking::Client,
+    core_props: PathBuf,
+    metadata: GameMetadata,
+    address: Arc<Mutex<Option<String>>>,
+    bindings: Vec<Value>,
+    backoff: Duration,
+    retry_at: Option<Instant>,
+    heartbeat: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl SteelSeriesClient {
+    pub fn new(metadata: GameMetadata, core_props: PathBuf) -> SteelSeriesClient {
+        SteelSeriesClient {
+            http: reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            core_props,
+            metadata,
+            address: Arc::new(Mutex::new(None)),
+            bindings: Vec::new(),
+            backoff: MIN_BACKOFF,
+            retry_at: None,
+            heartbeat: None,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.address.lock().unwrap().is_some()
+    }
+
+    /// Discovers GG, registers the game and (re-)binds every known event.
+    pub fn connect(&mut self) -> Result<(), anyhow::Error> {
+        *self.address.lock().unwrap() = None;
+        let address = read_core_props(&self.core_props)?;
+
+        let mut metadata = json!({
+            "game": self.metadata.game,
+            "game_display_name": self.metadata.game_display_name,
+            "developer": self.metadata.developer,
+        });
+        if let Some(icon_color_id) = self.metadata.icon_color_id {
+            metadata["icon_color_id"] = json!(icon_color_id);
+        }
+        if let Some(timer) = self.metadata.deinitialize_timer_length_ms {
+            metadata["deinitialize_timer_length_ms"] = json!(timer);
+        }
+        // Clears bindings left over from a previous run
+        send(
+            &self.http,
+            &address,
+            "remove_game",
+            &json!({"game": self.metadata.game}),
+        )
+        .ok();
+        send(&self.http, &address, "game_metadata", &metadata)?;
+        for binding in self.bindings.iter() {
+            send(&self.http, &address, "bind_game_event", binding)?;
+        }
+
+        *self.address.lock().unwrap() = Some(address);
+        self.backoff = MIN_BACKOFF;
+        self.retry_at = None;
+        Ok(())
+    }
+
+    pub fn bind_event<T: Serialize>(
+        &mut self,
+        event: &str,
+        min_value: Option<isize>,
+        max_value: Option<isize>,
+        icon_id: Option<u8>,
+        value_optional: Option<bool>,
+        handlers: Vec<T>,
+    ) -> Result<(), anyhow::Error> {
+        let mut binding = json!({
+            "game": self.metadata.game,
+            "event": event,
+            "handlers": handlers,
+        });
+        if let Some(min_value) = min_value {
+            binding["min_value"] = json!(min_value);
+        }
+        if let Some(max_value) = max_value {
+            binding["max_value"] = json!(max_value);
+        }
+        if let Some(icon_id) = icon_id {
+            binding["icon_id"] = json!(icon_id);
+        }
+        if let Some(value_optional) = value_optional {
+            binding["value_optional"] = json!(value_optional);
+        }
+        // Kept first, so a reconnect replays it even if this send fails
+        let replaced = self
+            .bindings
+            .iter()
+            .position(|existing| existing["event"] == binding["event"])
+            .map(|i| self.bindings.remove(i));
+        self.bindings.push(binding.clone());
+        if let Err(err) = self.send_or_reconnect("bind_game_event", binding) {
+            // Replaying a binding GG refused would fail every reconnect
+            self.bindings.pop();
+            self.bindings.extend(replaced);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    pub fn trigger_event(&mut self, event: &str, value: isize) -> Result<(), anyhow::Error> {
+        self.send_event(json!({
+            "game": self.metadata.game,
+            "event": event,
+            "data": {"value": value},
+        }))
+    }
+
+    pub fn trigger_event_frame(
+        &mut self,
+        event: &str,
+        value: isize,
+        frame: Value,
+    ) -> Result<(), anyhow::Error> {
+        self.send_event(json!({
+            "game": self.metadata.game,
+            "event": event,
+            "data": {"value": value, "frame": frame},
+        }))
+    }
+
+    pub fn start_heartbeat(&mut self) {
+        if self.heartbeat.is_some() {
+            return;
+        }
+        let active = Arc::new(AtomicBool::new(true));
+        let thread_active = Arc::clone(&active);
+        let address = Arc::clone(&self.address);
+        let http = self.http.clone();
+        let body = json!({"game": self.metadata.game});
+        let handle = thread::spawn(move || {
+            let mut last: Option<Instant> = None;
+            while thread_active.load(Ordering::Relaxed) {
+                if last.map_or(true, |last| last.elapsed() >= HEARTBEAT_INTERVAL) {
+                    let current = address.lock().unwrap().clone();
+                    if let Some(current) = current {
+                        send(&http, &current, "game_heartbeat", &body).ok();
+                    }
+                    last = Some(Instant::now());
+                }
+                thread::sleep(Duration::from_millis(250));
+            }
+        });
+        self.heartbeat = Some((active, handle));
+    }
+
+    pub fn stop_heartbeat(&mut self) {
+        if let Some((active, handle)) = self.heartbeat.take() {
+            active.store(false, Ordering::Relaxed);
+            handle.join().ok();
+        }
+    }
+
+    /// Unregisters the game from GG, which also drops its event bindings.
+    pub fn remove_game(&mut self) -> Result<(), anyhow::Error> {
+        let address = self.address.lock().unwrap().take();
+        if let Some(address) = address {
+            send(
+                &self.http,
+                &address,
+                "remove_game",
+                &json!({"game": self.metadata.game}),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn send_event(&mut self, event: Value) -> Result<(), anyhow::Error> {
+        self.send_or_reconnect("game_event", event)
+    }
+
+    /// Posts to GG, reconnecting when it can't be reached. While GG stays down the request
+    /// is dropped and reconnects back off. GG refusing the request is returned, reconnecting
+    /// wouldn't change its mind.
+    fn send_or_reconnect(&mut self, endpoint: &str, body: Value) -> Result<(), anyhow::Error> {
+        let address = self.address.lock().unwrap().clone();
+        if let Some(address) = address {
+            match send(&self.http, &address, endpoint, &body) {
+                Ok(_) => return Ok(()),
+                Err(err) if rejected(&err) => return Err(err),
+                // GG went away, start over
+                Err(_) => *self.address.lock().unwrap() = None,
+            }
+        }
+        if let Some(retry_at) = self.retry_at {
+            if Instant::now() < retry_at {
+                return Ok(());
+            }
+        }
+        let delivered = self.connect().and_then(|()| {
+            // Bindings are replayed by `connect`
+            if endpoint == "bind_game_event" {
+                return Ok(());
+            }
+            let address = self.address.lock().unwrap().clone().unwrap_or_default();
+            send(&self.http, &address, endpoint, &body).map(|_| ())
+        });
+        match delivered {
+            Ok(()) => Ok(()),
+            Err(err) if rejected(&err) => Err(err),
+            Err(_) => {
+                *self.address.lock().unwrap() = None;
+                self.retry_at = Some(Instant::now() + self.backoff);
+                self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for SteelSeriesClient {
+    fn drop(&mut self) {
+        self.stop_heartbeat();
+    }
+}
+
+fn send(
+    http: &reqwest::blocking::Client,
+    address: &str,
+    endpoint: &str,
+    body: &Value,
+) -> Result<Value, anyhow::Error> {
+    let text = http
+        .post(format!("http://{}/{}", address, endpoint))
+        .json(body)
+        .send()?
+        .text()?;
+    let error = match serde_json::from_str::<Value>(&text) {
+        Ok(response) => match response.get("error") {
+            Some(error) => error.to_string(),
+            None => return Ok(response),
+        },
+        Err(_) => text,
+    };
+    Err(anyhow::Error::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{} failed: {}", endpoint, error),
+    )))
+}
+
+/// Whether GG answered `send` with an error, as opposed to not answering at all.
+fn rejected(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|err| err.kind() == std::io::ErrorKind::InvalidData)
+}
@@ -1,6 +1,7 @@
 use console::Term;
-use gamesense::client::GameSenseClient;
+use hwinfo_steelseries_oled::client::{core_props_path, GameMetadata, SteelSeriesClient};
 use hwinfo_steelseries_oled::Hwinfo;
+use ini::Ini;
 
 pub fn connect_hwinfo(term: &Term) -> Result<Hwinfo, anyhow::Error> {
     match Hwinfo::new() {
@@ -23,26 +24,54 @@ pub fn connect_hwinfo(term: &Term) -> Result<Hwinfo, anyhow::Error> {
     }
 }
 
-pub fn connect_steelseries(term: &Term) -> Result<GameSenseClient, anyhow::Error> {
-    match GameSenseClient::new("HWINFO", "HWiNFO_Stats", "Ryan", None) {
-        Ok(c) => {
-            term.write_line("Connected to SteelSeries GG")?;
-            Ok(c)
-        }
-        Err(_e) => {
-            for i in (1..=3).rev() {
-                // term.clear_line()?;
-                term.clear_screen()?;
-                term.write_line(
-                    format!(
-                        "Can't connect to SteelSeries GG. Trying again in {} second.",
-                        i
-                    )
-                    .as_str(),
-                )?;
-                std::thread::sleep(std::time::Duration::from_secs(1));
+/// Reads the optional `[GameSense]` section, defaulting to the original "HWINFO" game.
+pub fn game_metadata(config_file: &Ini) -> Result<GameMetadata, anyhow::Error> {
+    let section = config_file.section(Some("GameSense"));
+    let get = |key: &str| section.and_then(|section| section.get(key));
+    let metadata = GameMetadata {
+        game: get("game").unwrap_or("HWINFO").to_string(),
+        game_display_name: get("game_display_name")
+            .unwrap_or("HWiNFO_Stats")
+            .to_string(),
+        developer: get("developer").unwrap_or("Ryan").to_string(),
+        icon_color_id: match get("icon_color_id") {
+            Some(icon_color_id) => Some(icon_color_id.parse::<u8>()?),
+            None => None,
+        },
+        deinitialize_timer_length_ms: match get("deinitialize_timer_length_ms") {
+            Some(timer) => Some(timer.parse::<u16>()?),
+            None => None,
+        },
+    };
+    metadata.validate()?;
+    Ok(metadata)
+}
+
+pub fn connect_steelseries(
+    term: &Term,
+    metadata: GameMetadata,
+) -> Result<SteelSeriesClient, anyhow::Error> {
+    let mut client = SteelSeriesClient::new(metadata, core_props_path()?);
+    loop {
+        match client.connect() {
+            Ok(()) => {
+                term.write_line("Connected to SteelSeries GG")?;
+                return Ok(client);
+            }
+            Err(_e) => {
+                for i in (1..=3).rev() {
+                    // term.clear_line()?;
+                    term.clear_screen()?;
+                    term.write_line(
+                        format!(
+                            "Can't connect to SteelSeries GG. Trying again in {} second.",
+                            i
+                        )
+                        .as_str(),
+                    )?;
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
             }
-            connect_steelseries(term)
         }
     }
 }
@@ -1,8 +1,3 @@
-use console::Term;
-use serde_json::Value;
-
-use crate::consts::DISPLAY_LINES;
-
 pub enum Console {
     SHOW,
     #[allow(dead_code)]
@@ -25,11 +20,3 @@ pub fn console_window(action: Console) {
         }
     }
 }
-
-pub fn display_value_in_console(term: &Term, value: &Value) -> anyhow::Result<()> {
-    term.clear_screen()?;
-    for i in 0..DISPLAY_LINES {
-        term.write_line(&value[format!("line{}", i + 1)].to_string())?;
-    }
-    Ok(())
-}
@@ -0,0 +1,27 @@
+//! Per-second rates of cumulative readings like `Total DL`, for `rate_N`.
+
+/// A reading that only counts up, rolling over to 0 at `wrap` when set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counter {
+    pub wrap: Option<f64>,
+}
+
+impl Counter {
+    /// Change per second from `previous` to `current`, `seconds` apart. A counter going
+    /// backwards has rolled over at `wrap`, or without it was reset, which gives `None`.
+    pub fn rate(&self, previous: f64, current: f64, seconds: f64) -> Option<f64> {
+        if seconds <= 0.0 || previous.is_nan() || current.is_nan() {
+            return None;
+        }
+        let delta = if current >= previous {
+            current - previous
+        } else {
+            self.wrap? - previous + current
+        };
+        // A wrap smaller than the counter is a reset too
+        if delta < 0.0 {
+            return None;
+        }
+        Some(delta / seconds)
+    }
+}
@@ -0,0 +1,68 @@
+//! SteelSeries OLED screens and what fits on them.
+
+/// A GameSense screen device type with the layout the text pages use on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeviceProfile {
+    /// Name used in `conf.ini`, also the GameSense `device-type` of text handlers.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub width: usize,
+    pub height: usize,
+    /// Text lines GG can show at once.
+    pub lines: usize,
+}
+
+impl DeviceProfile {
+    pub fn from_name(name: &str) -> Option<DeviceProfile> {
+        PROFILES
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+            .copied()
+    }
+
+    /// GameSense `device-type` for image frames, which always need the exact size.
+    pub fn image_device_type(&self) -> String {
+        format!("screened-{}x{}", self.width, self.height)
+    }
+}
+
+/// `screened` matches every screen and was the only option before profiles.
+pub const DEFAULT_PROFILE: &str = "screened";
+
+pub const PROFILES: [DeviceProfile; 5] = [
+    DeviceProfile {
+        name: "screened",
+        description: "Any SteelSeries screen",
+        width: 128,
+        height: 40,
+        lines: 3,
+    },
+    DeviceProfile {
+        name: "screened-128x36",
+        description: "Rival 700 / 710",
+        width: 128,
+        height: 36,
+        lines: 2,
+    },
+    DeviceProfile {
+        name: "screened-128x40",
+        description: "Apex 7 / Apex Pro / Apex 5",
+        width: 128,
+        height: 40,
+        lines: 2,
+    },
+    DeviceProfile {
+        name: "screened-128x48",
+        description: "Arctis Pro Wireless",
+        width: 128,
+        height: 48,
+        lines: 3,
+    },
+    DeviceProfile {
+        name: "screened-128x52",
+        description: "Arctis Pro + GameDAC / Arctis Nova Pro",
+        width: 128,
+        height: 52,
+        lines: 3,
+    },
+];
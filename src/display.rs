@@ -0,0 +1,469 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use console::Term;
+use serde_json::{json, Value};
+
+use crate::alert::Severity;
+use crate::bitmap::Framebuffer;
+use crate::client::SteelSeriesClient;
+use crate::device::DeviceProfile;
+use crate::lighting::{AlertEffects, ColorGradient};
+use crate::steelseries::{
+    color_handler, gradient_handler, image_handler, lines_handler, progress_bar_line,
+    tactile_handler, text_line,
+};
+
+/// How long the goodbye frame stays up before the game is removed.
+const GOODBYE_TIME: Duration = Duration::from_secs(1);
+
+/// One rendered screen worth of text, independent of where it ends up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// 0-based screen the frame is for, see [`ScreenLayout`].
+    pub screen: usize,
+    /// 1-based page number, `None` for status frames such as "Disconnected from HWiNFO".
+    pub page: Option<usize>,
+    pub lines: Vec<String>,
+    /// Set for alert frames, which go to the `ALERT` event instead of the page.
+    pub alert: Option<Severity>,
+    /// Set for bitmap pages, `lines` then only feed the text outputs.
+    pub image: Option<Framebuffer>,
+    /// Fill (0 to 1) of the lines shown as progress bars, by line. The text in `lines` is
+    /// still what the console and file outputs show.
+    pub bars: Vec<Option<f64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineKind {
+    Text,
+    ProgressBar,
+}
+
+/// How a page is sent to GameSense.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PageRender {
+    Text { lines: Vec<LineKind> },
+    Bitmap { width: usize, height: usize },
+}
+
+impl PageRender {
+    /// Plain text page with `lines` lines.
+    pub fn text(lines: usize) -> PageRender {
+        PageRender::Text {
+            lines: vec![LineKind::Text; lines],
+        }
+    }
+}
+
+/// One physical screen and the pages shown on it. Each screen gets its own GameSense
+/// events, so several devices can show different pages at the same time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScreenLayout {
+    pub device: DeviceProfile,
+    pub pages: Vec<PageRender>,
+}
+
+impl Frame {
+    pub fn page(page: usize, lines: Vec<String>) -> Frame {
+        Frame {
+            screen: 0,
+            page: Some(page),
+            lines,
+            alert: None,
+            image: None,
+            bars: Vec::new(),
+        }
+    }
+
+    pub fn status(lines: Vec<String>) -> Frame {
+        Frame {
+            screen: 0,
+            page: None,
+            lines,
+            alert: None,
+            image: None,
+            bars: Vec::new(),
+        }
+    }
+
+    /// Sensors past their thresholds, see [`crate::alert::alert_lines`].
+    pub fn alert(severity: Severity, lines: Vec<String>) -> Frame {
+        Frame {
+            screen: 0,
+            page: None,
+            lines,
+            alert: Some(severity),
+            image: None,
+            bars: Vec::new(),
+        }
+    }
+
+    /// GameSense event the frame is sent to.
+    pub fn event(&self) -> String {
+        match self.alert {
+            Some(_) => alert_event_name(self.screen),
+            None => event_name(self.screen, self.page),
+        }
+    }
+
+    pub fn on_screen(mut self, screen: usize) -> Frame {
+        self.screen = screen;
+        self
+    }
+
+    pub fn with_image(mut self, image: Framebuffer) -> Frame {
+        self.image = Some(image);
+        self
+    }
+
+    pub fn with_bars(mut self, bars: Vec<Option<f64>>) -> Frame {
+        self.bars = bars;
+        self
+    }
+
+    /// `{"line1": .., "line2": .., ..}` as expected by the `lines_handler` screen handler,
+    /// or `{"image-data-WxH": [..]}` for bitmap pages. Progress bar lines carry a number
+    /// from 0 to 100 instead of text.
+    pub fn to_value(&self) -> Value {
+        if let Some(image) = &self.image {
+            return json!({
+                format!("image-data-{}x{}", image.width, image.height): image.to_image_data()
+            });
+        }
+        let mut value = json!({});
+        for (i, line) in self.lines.iter().enumerate() {
+            value[format!("line{}", i + 1)] = match self.bars.get(i) {
+                Some(Some(fraction)) => json!((fraction.clamp(0.0, 1.0) * 100.0).round() as u8),
+                _ => json!(line),
+            };
+        }
+        value
+    }
+}
+
+/// `PAGE1`/`ERROR` for the first screen, `SCREEN2_PAGE1`/`SCREEN2_ERROR` for the second...
+pub fn event_name(screen: usize, page: Option<usize>) -> String {
+    let prefix = match screen {
+        0 => String::new(),
+        screen => format!("SCREEN{}_", screen + 1),
+    };
+    match page {
+        Some(page) => format!("{}PAGE{}", prefix, page),
+        None => format!("{}ERROR", prefix),
+    }
+}
+
+/// `ALERT` for the first screen, `SCREEN2_ALERT` for the second...
+pub fn alert_event_name(screen: usize) -> String {
+    match screen {
+        0 => String::from("ALERT"),
+        screen => format!("SCREEN{}_ALERT", screen + 1),
+    }
+}
+
+/// Colors the alert zone, 1 for warnings and 2 for critical alerts, 0 turns it off.
+pub const ALERT_COLOR_EVENT: &str = "ALERT_COLOR";
+
+/// Vibration when an alert of that severity is raised.
+pub fn alert_tactile_event_name(severity: Severity) -> String {
+    format!("ALERT_{}", severity.name())
+}
+
+/// `LIGHTING3` for the gradient of `sensor_3` in `[Lighting]`.
+pub fn gradient_event_name(k: usize) -> String {
+    format!("LIGHTING{}", k)
+}
+
+pub trait DisplaySink {
+    fn show(&mut self, frame: &Frame) -> Result<(), anyhow::Error>;
+
+    /// Worst alert shown on any screen, called once per tick after the frames.
+    fn alert(&mut self, _severity: Option<Severity>) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Position (0 to 1) of the reading behind gradient `k` within its range, once per tick.
+    fn gradient(&mut self, _k: usize, _fraction: f64) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Called once when the app exits.
+    fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+/// Sends frames to SteelSeries GG.
+pub struct GameSenseSink {
+    client: SteelSeriesClient,
+    screens: usize,
+    value: isize,
+    alert_effects: Option<AlertEffects>,
+    severity: Option<Severity>,
+}
+
+impl GameSenseSink {
+    /// Binds the page events, the status event and the alert event of every screen, then
+    /// starts the heartbeat.
+    pub fn new(
+        mut client: SteelSeriesClient,
+        screens: &[ScreenLayout],
+    ) -> Result<GameSenseSink, anyhow::Error> {
+        for (screen, layout) in screens.iter().enumerate() {
+            let device = &layout.device;
+            for (i, render) in layout.pages.iter().enumerate() {
+                let handler = match render {
+                    PageRender::Text { lines } => lines_handler(
+                        device.name,
+                        3,
+                        lines
+                            .iter()
+                            .enumerate()
+                            .map(|(i, kind)| {
+                                let key = format!("line{}", i + 1);
+                                match kind {
+                                    LineKind::Text => text_line(&key, None),
+                                    LineKind::ProgressBar => progress_bar_line(&key),
+                                }
+                            })
+                            .collect(),
+                    ),
+                    PageRender::Bitmap { .. } => image_handler(3, device),
+                };
+                client.bind_event(
+                    event_name(screen, Some(i + 1)).as_str(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    vec![handler],
+                )?;
+            }
+            let handler = lines_handler(
+                device.name,
+                3,
+                (1..=device.lines)
+                    .map(|line| text_line(&format!("line{}", line), None))
+                    .collect(),
+            );
+            client.bind_event(
+                event_name(screen, None).as_str(),
+                None,
+                None,
+                None,
+                None,
+                vec![handler],
+            )?;
+            // Same lines in bold, so an alert stands out from the pages
+            let handler = lines_handler(
+                device.name,
+                3,
+                (1..=device.lines)
+                    .map(|line| text_line(&format!("line{}", line), Some(true)))
+                    .collect(),
+            );
+            client.bind_event(
+                alert_event_name(screen).as_str(),
+                None,
+                None,
+                None,
+                None,
+                vec![handler],
+            )?;
+        }
+        client.start_heartbeat();
+        Ok(GameSenseSink {
+            client,
+            screens: screens.len(),
+            value: 0,
+            alert_effects: None,
+            severity: None,
+        })
+    }
+
+    /// Binds the color and tactile alert events for the severities `effects` has one for.
+    pub fn bind_alert_effects(&mut self, effects: AlertEffects) -> Result<(), anyhow::Error> {
+        if effects.has_color() {
+            let ranges = [(1, 1, effects.warn_color), (2, 2, effects.crit_color)]
+                .into_iter()
+                .filter_map(|(low, high, color)| color.map(|color| (low, high, color)))
+                .collect::<Vec<_>>();
+            let frequency = Some(effects.flash).filter(|flash| *flash > 0.0);
+            self.client.bind_event(
+                ALERT_COLOR_EVENT,
+                Some(0),
+                Some(2),
+                None,
+                None,
+                vec![color_handler(&effects.color_zone, &ranges, frequency)],
+            )?;
+        }
+        for (severity, pattern) in [
+            (Severity::Warning, &effects.warn_tactile),
+            (Severity::Critical, &effects.crit_tactile),
+        ] {
+            if let Some(pattern) = pattern {
+                self.client.bind_event(
+                    alert_tactile_event_name(severity).as_str(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    vec![tactile_handler(&effects.tactile_zone, pattern)],
+                )?;
+            }
+        }
+        self.alert_effects = Some(effects);
+        Ok(())
+    }
+
+    pub fn bind_gradient(
+        &mut self,
+        k: usize,
+        gradient: &ColorGradient,
+    ) -> Result<(), anyhow::Error> {
+        self.client.bind_event(
+            gradient_event_name(k).as_str(),
+            Some(0),
+            Some(100),
+            None,
+            None,
+            vec![gradient_handler(
+                &gradient.zone,
+                &gradient.zero,
+                &gradient.hundred,
+            )],
+        )
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+}
+
+impl DisplaySink for GameSenseSink {
+    fn show(&mut self, frame: &Frame) -> Result<(), anyhow::Error> {
+        self.client
+            .trigger_event_frame(frame.event().as_str(), self.value, frame.to_value())?;
+        self.value = self.value.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Keeps the alert color up to date and vibrates when an alert gets worse.
+    fn alert(&mut self, severity: Option<Severity>) -> Result<(), anyhow::Error> {
+        let effects = match &self.alert_effects {
+            Some(effects) => effects,
+            None => return Ok(()),
+        };
+        if effects.has_color() {
+            let value = match severity {
+                None => 0,
+                Some(Severity::Warning) => 1,
+                Some(Severity::Critical) => 2,
+            };
+            self.client.trigger_event(ALERT_COLOR_EVENT, value)?;
+        }
+        if let Some(raised) = severity.filter(|severity| Some(*severity) > self.severity) {
+            let pattern = match raised {
+                Severity::Warning => &effects.warn_tactile,
+                Severity::Critical => &effects.crit_tactile,
+            };
+            if pattern.is_some() {
+                self.client
+                    .trigger_event(alert_tactile_event_name(raised).as_str(), self.value)?;
+                self.value = self.value.wrapping_add(1);
+            }
+        }
+        self.severity = severity;
+        Ok(())
+    }
+
+    fn gradient(&mut self, k: usize, fraction: f64) -> Result<(), anyhow::Error> {
+        let value = (fraction.clamp(0.0, 1.0) * 100.0).round() as isize;
+        self.client
+            .trigger_event(gradient_event_name(k).as_str(), value)
+    }
+
+    /// Shows a goodbye frame and unregisters the game so GG doesn't keep the last
+    /// readings on screen. The frame is held for a moment, as removing the game clears
+    /// the screen.
+    fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        // Showing would try to reconnect to a GG that is gone
+        if self.client.is_connected() {
+            for screen in 0..self.screens {
+                let goodbye = Frame::status(vec![
+                    String::from("HWiNFO"),
+                    String::from("Closed"),
+                    String::new(),
+                ])
+                .on_screen(screen);
+                self.show(&goodbye)?;
+            }
+            thread::sleep(GOODBYE_TIME);
+        }
+        self.client.stop_heartbeat();
+        self.client.remove_game()
+    }
+}
+
+/// Mirrors frames in the console window.
+pub struct ConsoleSink {
+    term: Term,
+    screens: Vec<Vec<String>>,
+}
+
+impl ConsoleSink {
+    pub fn new(term: Term) -> ConsoleSink {
+        ConsoleSink {
+            term,
+            screens: Vec::new(),
+        }
+    }
+}
+
+impl DisplaySink for ConsoleSink {
+    fn show(&mut self, frame: &Frame) -> Result<(), anyhow::Error> {
+        self.term.clear_screen()?;
+        for line in all_screens(&mut self.screens, frame) {
+            self.term.write_line(&line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Overwrites a text file with the current frame, e.g. for a stream overlay.
+pub struct FileSink {
+    path: PathBuf,
+    screens: Vec<Vec<String>>,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> FileSink {
+        FileSink {
+            path,
+            screens: Vec::new(),
+        }
+    }
+}
+
+impl DisplaySink for FileSink {
+    fn show(&mut self, frame: &Frame) -> Result<(), anyhow::Error> {
+        let mut contents = all_screens(&mut self.screens, frame).join("\n");
+        contents.push('\n');
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
+
+/// Remembers `frame` as the latest of its screen and returns the lines of every screen,
+/// with a blank line between screens.
+fn all_screens(screens: &mut Vec<Vec<String>>, frame: &Frame) -> Vec<String> {
+    if screens.len() <= frame.screen {
+        screens.resize(frame.screen + 1, Vec::new());
+    }
+    screens[frame.screen] = frame.lines.clone();
+    screens.join(&String::new())
+}
@@ -0,0 +1,274 @@
+//! Small arithmetic expressions over HWiNFO readings, for `sensor_N="EXPR;..."`.
+//!
+//! Readings are written `{Sensor;Reading}`, where either part can use `*` to match several
+//! readings. `+ - * /`, parentheses and the functions `max`, `min`, `avg`, `sum` and `abs`
+//! are supported, e.g. `{CPU*;CPU Package Power} + {GPU*;GPU Power}` or
+//! `max({CPU*: DTS;Core * Temp*})`.
+
+use anyhow::bail;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Max,
+    Min,
+    Avg,
+    Sum,
+    Abs,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Function> {
+        match name.to_lowercase().as_str() {
+            "max" => Some(Function::Max),
+            "min" => Some(Function::Min),
+            "avg" => Some(Function::Avg),
+            "sum" => Some(Function::Sum),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, values: &[f64]) -> Result<f64, anyhow::Error> {
+        if values.is_empty() {
+            bail!("{:?} of no readings", self);
+        }
+        Ok(match self {
+            Function::Max => values.iter().copied().fold(f64::MIN, f64::max),
+            Function::Min => values.iter().copied().fold(f64::MAX, f64::min),
+            Function::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            Function::Sum => values.iter().sum(),
+            Function::Abs => match values {
+                [value] => value.abs(),
+                _ => bail!("abs takes a single value"),
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Reading { sensor: String, reading: String },
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, anyhow::Error> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let expr = parser.sum()?;
+        parser.skip_spaces();
+        if parser.position < parser.chars.len() {
+            bail!(
+                "Unexpected \"{}\" in expression: {}",
+                parser.chars[parser.position],
+                text
+            );
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the expression, `readings` returns the values of the readings matching a
+    /// sensor and reading pattern.
+    pub fn eval(&self, readings: &dyn Fn(&str, &str) -> Vec<f64>) -> Result<f64, anyhow::Error> {
+        Ok(match self {
+            Expr::Number(number) => *number,
+            Expr::Reading { sensor, reading } => match readings(sensor, reading)[..] {
+                [value] => value,
+                [] => bail!("Reading not found: {};{}", sensor, reading),
+                _ => bail!(
+                    "{};{} matches several readings, use max, min, avg or sum",
+                    sensor,
+                    reading
+                ),
+            },
+            Expr::Neg(expr) => -expr.eval(readings)?,
+            Expr::Add(a, b) => a.eval(readings)? + b.eval(readings)?,
+            Expr::Sub(a, b) => a.eval(readings)? - b.eval(readings)?,
+            Expr::Mul(a, b) => a.eval(readings)? * b.eval(readings)?,
+            Expr::Div(a, b) => a.eval(readings)? / b.eval(readings)?,
+            Expr::Call(function, args) => {
+                // Patterns given to a function count every reading they match
+                let mut values = Vec::new();
+                for arg in args {
+                    match arg {
+                        Expr::Reading { sensor, reading } => {
+                            values.extend(readings(sensor, reading))
+                        }
+                        arg => values.push(arg.eval(readings)?),
+                    }
+                }
+                function.apply(&values)?
+            }
+        })
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of characters.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<&str>>();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn skip_spaces(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.chars.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), anyhow::Error> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => bail!("Expected \"{}\" but found \"{}\"", expected, c),
+            None => bail!("Expected \"{}\" at the end of the expression", expected),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, anyhow::Error> {
+        let mut expr = self.product()?;
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.position += 1;
+                    expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+                }
+                Some('-') => {
+                    self.position += 1;
+                    expr = Expr::Sub(Box::new(expr), Box::new(self.product()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, anyhow::Error> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.position += 1;
+                    expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+                }
+                Some('/') => {
+                    self.position += 1;
+                    expr = Expr::Div(Box::new(expr), Box::new(self.unary()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, anyhow::Error> {
+        if self.peek() == Some('-') {
+            self.position += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, anyhow::Error> {
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some('{') => {
+                self.position += 1;
+                let start = self.position;
+                while self.chars.get(self.position).is_some_and(|c| *c != '}') {
+                    self.position += 1;
+                }
+                let inner = self.chars[start..self.position].iter().collect::<String>();
+                self.expect('}')?;
+                match inner.split_once(';') {
+                    Some((sensor, reading)) => Ok(Expr::Reading {
+                        sensor: sensor.to_string(),
+                        reading: reading.to_string(),
+                    }),
+                    None => bail!("Expected {{Sensor;Reading}}, got {{{}}}", inner),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.position;
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+                {
+                    self.position += 1;
+                }
+                let number = self.chars[start..self.position].iter().collect::<String>();
+                match number.parse::<f64>() {
+                    Ok(number) => Ok(Expr::Number(number)),
+                    Err(_) => bail!("Invalid number: {}", number),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.position;
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_alphabetic())
+                {
+                    self.position += 1;
+                }
+                let name = self.chars[start..self.position].iter().collect::<String>();
+                let function = match Function::from_name(&name) {
+                    Some(function) => function,
+                    None => bail!("Unknown function: {}", name),
+                };
+                self.expect('(')?;
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(',') {
+                    self.position += 1;
+                    args.push(self.sum()?);
+                }
+                self.expect(')')?;
+                Ok(Expr::Call(function, args))
+            }
+            Some(c) => bail!("Unexpected \"{}\" in expression", c),
+            None => bail!("Expression ends too early"),
+        }
+    }
+}
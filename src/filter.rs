@@ -0,0 +1,107 @@
+//! Smoothing of jittery readings for `filter_N`, e.g. `avg:5` or `peak:0.1`.
+
+use anyhow::bail;
+
+use crate::sparkline::SampleBuffer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Mean of the last N ticks.
+    Average(usize),
+    /// Exponential smoothing, the weight of the newest reading between 0 and 1.
+    Exponential(f64),
+    /// Median of the last N ticks, ignores single spikes.
+    Median(usize),
+    /// Rises with the reading at once, then falls back by this share of the gap each tick.
+    PeakHold(f64),
+}
+
+impl Filter {
+    /// `avg:N`, `ema:ALPHA`, `median:N` or `peak:DECAY`.
+    pub fn parse(text: &str) -> Result<Filter, anyhow::Error> {
+        let (name, parameter) = match text.trim().split_once(':') {
+            Some((name, parameter)) => (name.trim().to_lowercase(), parameter.trim()),
+            None => bail!(
+                "Unknown filter \"{}\", expected avg:N, ema:ALPHA, median:N or peak:DECAY",
+                text
+            ),
+        };
+        let ticks = || match parameter.parse::<usize>() {
+            Ok(ticks) if ticks > 0 => Ok(ticks),
+            _ => bail!("{} needs a number of ticks, got \"{}\"", name, parameter),
+        };
+        let share = || match parameter.parse::<f64>() {
+            Ok(share) if share > 0.0 && share <= 1.0 => Ok(share),
+            _ => bail!(
+                "{} needs a number between 0 and 1, got \"{}\"",
+                name,
+                parameter
+            ),
+        };
+        Ok(match name.as_str() {
+            "avg" | "average" => Filter::Average(ticks()?),
+            "ema" => Filter::Exponential(share()?),
+            "median" => Filter::Median(ticks()?),
+            "peak" => Filter::PeakHold(share()?),
+            _ => bail!(
+                "Unknown filter \"{}\", expected avg, ema, median or peak",
+                name
+            ),
+        })
+    }
+}
+
+/// A [`Filter`] and the recent readings it needs, kept from tick to tick.
+#[derive(Clone, Debug)]
+pub struct FilterState {
+    filter: Filter,
+    samples: SampleBuffer,
+    value: Option<f64>,
+}
+
+impl FilterState {
+    pub fn new(filter: Filter) -> FilterState {
+        let window = match filter {
+            Filter::Average(ticks) | Filter::Median(ticks) => ticks,
+            Filter::Exponential(_) | Filter::PeakHold(_) => 1,
+        };
+        FilterState {
+            filter,
+            samples: SampleBuffer::new(window),
+            value: None,
+        }
+    }
+
+    /// Feeds the reading of this tick and returns the filtered value. A missing (NaN)
+    /// reading keeps the last value rather than spoiling the window.
+    pub fn push(&mut self, value: f64) -> Option<f64> {
+        if value.is_nan() {
+            return self.value;
+        }
+        self.samples.push(value);
+        self.value = Some(match (self.filter, self.value) {
+            (Filter::Average(_), _) => {
+                self.samples.samples().sum::<f64>() / self.samples.len() as f64
+            }
+            (Filter::Median(_), _) => {
+                let mut sorted = self.samples.samples().copied().collect::<Vec<f64>>();
+                sorted.sort_by(f64::total_cmp);
+                let middle = sorted.len() / 2;
+                if sorted.len() % 2 == 0 {
+                    (sorted[middle - 1] + sorted[middle]) / 2.0
+                } else {
+                    sorted[middle]
+                }
+            }
+            (Filter::Exponential(alpha), Some(last)) => last + alpha * (value - last),
+            (Filter::PeakHold(decay), Some(peak)) if value < peak => peak - decay * (peak - value),
+            (Filter::Exponential(_) | Filter::PeakHold(_), _) => value,
+        });
+        self.value
+    }
+
+    /// Filtered value as of the last reading, `None` before the first one.
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
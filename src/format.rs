@@ -0,0 +1,111 @@
+//! Number formats for `format_N`, e.g. `.3` for voltages or `,.0` for large counts.
+
+use anyhow::bail;
+
+/// A printf-like spec: flags (`+` for a sign on positive numbers, `0` to pad with zeros,
+/// `,` or `'` for thousands separators), a minimum width and `.precision`. `%` and a
+/// trailing `f`, or Rust's `{:` and `}`, are optional, so `%+.1f`, `{:02.0}` and `+.1`
+/// are the same kind of spec.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NumberFormat {
+    /// Digits after the point, `None` prints as many as the value needs.
+    pub precision: Option<usize>,
+    /// Minimum characters, padded on the left.
+    pub width: usize,
+    pub zero_pad: bool,
+    pub sign: bool,
+    pub thousands: bool,
+}
+
+impl NumberFormat {
+    pub fn parse(spec: &str) -> Result<NumberFormat, anyhow::Error> {
+        let trimmed = spec.trim();
+        let inner = if let Some(rust) = trimmed.strip_prefix("{:") {
+            match rust.strip_suffix('}') {
+                Some(inner) => inner,
+                None => bail!("Invalid format \"{}\": missing }}", spec),
+            }
+        } else {
+            let printf = trimmed.strip_prefix('%').unwrap_or(trimmed);
+            printf.strip_suffix('f').unwrap_or(printf)
+        };
+
+        let mut format = NumberFormat::default();
+        let mut chars = inner.chars().peekable();
+        while let Some(flag) = chars.peek() {
+            match flag {
+                '+' => format.sign = true,
+                '0' => format.zero_pad = true,
+                ',' | '\'' => format.thousands = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let rest = chars.collect::<String>();
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest.as_str(), None),
+        };
+        if !width.is_empty() {
+            format.width = match width.parse::<usize>() {
+                Ok(width) => width,
+                Err(_) => bail!("Invalid width in format \"{}\"", spec),
+            };
+        }
+        if let Some(precision) = precision {
+            format.precision = match precision.parse::<usize>() {
+                Ok(precision) => Some(precision),
+                Err(_) => bail!("Invalid precision in format \"{}\"", spec),
+            };
+        }
+        Ok(format)
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        let digits = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value.abs()),
+            None => format!("{}", value.abs()),
+        };
+        let digits = if self.thousands {
+            let (whole, fraction) = match digits.split_once('.') {
+                Some((whole, fraction)) => (whole.to_string(), format!(".{}", fraction)),
+                None => (digits.clone(), String::new()),
+            };
+            group_thousands(&whole) + &fraction
+        } else {
+            digits
+        };
+        // Rounded to zero, no "-0"
+        let negative = value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0');
+        let sign = if negative {
+            "-"
+        } else if self.sign {
+            "+"
+        } else {
+            ""
+        };
+        let padding = self
+            .width
+            .saturating_sub(sign.len() + digits.chars().count());
+        if self.zero_pad {
+            format!("{}{}{}", sign, "0".repeat(padding), digits)
+        } else {
+            format!("{}{}{}", " ".repeat(padding), sign, digits)
+        }
+    }
+}
+
+fn group_thousands(whole: &str) -> String {
+    if !whole.chars().all(|c| c.is_ascii_digit()) {
+        // inf, NaN
+        return whole.to_string();
+    }
+    let mut grouped = String::new();
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
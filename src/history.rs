@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Local;
+use hwinfo_steelseries_oled::device::PROFILES;
+use hwinfo_steelseries_oled::display::Frame;
+use hwinfo_steelseries_oled::log_file::{csv_row, RotatingFile};
+use hwinfo_steelseries_oled::Hwinfo;
+use serde_json::json;
+
+#[derive(PartialEq)]
+pub enum HistoryFormat {
+    Csv,
+    Jsonl,
+}
+
+pub struct HistoryConfig {
+    pub path: PathBuf,
+    pub format: HistoryFormat,
+    pub max_bytes: u64,
+    pub max_age: Option<Duration>,
+    pub keep: usize,
+    pub readings: bool,
+}
+
+impl HistoryConfig {
+    /// Reads the optional `[History]` section. Returns `None` when the section is missing
+    /// or `enabled=false`.
+    pub fn from_ini(config_file: &ini::Ini) -> Result<Option<HistoryConfig>, anyhow::Error> {
+        let section = match config_file.section(Some("History")) {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let enabled = match section.get("enabled") {
+            Some(enabled) => enabled.parse::<bool>()?,
+            None => true,
+        };
+        if !enabled {
+            return Ok(None);
+        }
+        let format = match section.get("format") {
+            Some(format) => match format.to_lowercase().as_str() {
+                "csv" => HistoryFormat::Csv,
+                "jsonl" | "json" => HistoryFormat::Jsonl,
+                _ => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("History format must be \"csv\" or \"jsonl\", got \"{}\"", format),
+                    )))
+                }
+            },
+            None => HistoryFormat::Csv,
+        };
+        let default_path = match format {
+            HistoryFormat::Csv => "history.csv",
+            HistoryFormat::Jsonl => "history.jsonl",
+        };
+        let readings = match section.get("log") {
+            Some(log) => match log.to_lowercase().as_str() {
+                "lines" => false,
+                "readings" => true,
+                _ => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("History log must be \"lines\" or \"readings\", got \"{}\"", log),
+                    )))
+                }
+            },
+            None => false,
+        };
+        Ok(Some(HistoryConfig {
+            path: PathBuf::from(section.get("path").unwrap_or(default_path)),
+            format,
+            max_bytes: match section.get("max_size_kb") {
+                Some(max_size) => max_size.parse::<u64>()? * 1024,
+                None => 1024 * 1024,
+            },
+            max_age: match section.get("max_age_hours") {
+                Some(max_age) => Some(Duration::from_secs(max_age.parse::<u64>()? * 3600)),
+                None => None,
+            },
+            keep: match section.get("keep") {
+                Some(keep) => keep.parse::<usize>()?,
+                None => 5,
+            },
+            readings,
+        }))
+    }
+}
+
+/// Appends every rendered frame (or the page readings) to a rotating CSV/JSON-lines file.
+pub struct HistoryLogger {
+    config: HistoryConfig,
+    file: RotatingFile,
+}
+
+impl HistoryLogger {
+    pub fn new(config: HistoryConfig) -> HistoryLogger {
+        let file = RotatingFile::new(
+            config.path.clone(),
+            config.max_bytes,
+            config.max_age,
+            config.keep,
+        );
+        HistoryLogger { config, file }
+    }
+
+    /// Logs the readings when `log=readings` and the pages have any (summary pages don't),
+    /// otherwise the frame lines.
+    pub fn log(
+        &mut self,
+        frame: &Frame,
+        hwinfo: &Hwinfo,
+        readings: &[(String, String)],
+    ) -> Result<(), anyhow::Error> {
+        let timestamp = Local::now().to_rfc3339();
+        let (header, row) = if self.config.readings && !readings.is_empty() {
+            let mut header = vec!["timestamp".to_string()];
+            let mut row = vec![timestamp.clone()];
+            let mut object = json!({});
+            for (sensor, reading) in readings {
+                let key = format!("{};{}", sensor, reading);
+                let reading_value = hwinfo.get(sensor, reading).map(|r| r.value);
+                header.push(key.clone());
+                row.push(match reading_value {
+                    Some(reading_value) => reading_value.to_string(),
+                    None => String::new(),
+                });
+                object[key] = json!(reading_value);
+            }
+            match self.config.format {
+                HistoryFormat::Csv => (Some(csv_row(&header)), csv_row(&row)),
+                HistoryFormat::Jsonl => (
+                    None,
+                    json!({"timestamp": timestamp, "readings": object}).to_string(),
+                ),
+            }
+        } else {
+            let page = frame.page.unwrap_or(0);
+            let lines = frame.lines.clone();
+            match self.config.format {
+                HistoryFormat::Csv => {
+                    // As many columns as the tallest screen, so the header stays the same
+                    // for status and alert frames
+                    let columns = PROFILES
+                        .iter()
+                        .map(|profile| profile.lines)
+                        .max()
+                        .unwrap_or(0);
+                    let mut header = vec!["timestamp".to_string(), "page".to_string()];
+                    header.extend((1..=columns).map(|i| format!("line{}", i)));
+                    let mut row = vec![timestamp, page.to_string()];
+                    row.extend(lines);
+                    row.resize(header.len(), String::new());
+                    (Some(csv_row(&header)), csv_row(&row))
+                }
+                HistoryFormat::Jsonl => (
+                    None,
+                    json!({"timestamp": timestamp, "page": page, "lines": lines}).to_string(),
+                ),
+            }
+        };
+        self.file.append(header.as_deref(), &row)
+    }
+}
@@ -1,4 +1,26 @@
+pub mod alert;
+pub mod bitmap;
+pub mod client;
+pub mod consts;
+pub mod counter;
+pub mod device;
+pub mod display;
+pub mod expr;
+pub mod filter;
+pub mod font;
+pub mod format;
+pub mod lighting;
+pub mod log_file;
+pub mod mqtt_client;
+pub mod screen;
+pub mod sparkline;
+pub mod steelseries;
+pub mod text;
+pub mod units;
+pub mod utils;
+
 use anyhow;
+use counter::Counter;
 use std::io::{Error, ErrorKind};
 use std::os::windows::ffi::OsStrExt;
 use std::{collections::HashMap, ffi::OsStr, iter::once};
@@ -53,6 +75,14 @@ impl PartialEq for HwinfoSensorsReadingElement {
 }
 impl Eq for HwinfoSensorsReadingElement {}
 
+impl HwinfoSensorsReadingElement {
+    pub fn unit(&self) -> String {
+        String::from_utf8_lossy(&self.utf_unit)
+            .trim_matches(char::from(0))
+            .to_string()
+    }
+}
+
 #[allow(dead_code)]
 #[repr(C, align(1))]
 #[derive(Hash, Clone, Copy)]
@@ -71,6 +101,7 @@ impl PartialEq for HwinfoSensorsSensorElement {
 impl Eq for HwinfoSensorsSensorElement {}
 
 #[allow(dead_code)]
+#[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 struct HwinfoSensorsSharedMem2 {
     dw_signature: u32,
@@ -112,15 +143,17 @@ impl Eq for MasterReadings {}
 
 #[derive(Clone)]
 pub struct Hwinfo {
-    num_reading_elements: u32,
-    offset_reading_section: u32,
-    size_reading_section: u32,
     shared_memory_name: Vec<u16>,
     pub master_sensor_names: Box<Vec<String>>,
     pub master_label_user: Box<Vec<String>>,
     // pub master_readings: Box<HashMap<String, HashMap<String, (String, [f64; 4])>>>,
     // pub new_master_reading: Box<HashMap<HwinfoSensorsSensorElement, HashMap<String, HwinfoSensorsReadingElement>>>
     pub master_readings: Box<MasterReadings>,
+    /// When HWiNFO polled the current and the previous readings, in seconds.
+    poll_time: i64,
+    previous_poll_time: i64,
+    /// Value at the previous poll of every reading passed to [`Hwinfo::track_counter`].
+    previous_counters: HashMap<(String, String), Option<f64>>,
 }
 impl PartialEq for Hwinfo {
     fn eq(&self, other: &Self) -> bool {
@@ -174,11 +207,8 @@ impl Hwinfo {
                 .1[0]
         };
         let num_sensors = hwinfo_memory.dw_num_sensor_elements;
-        let num_reading_elements = hwinfo_memory.dw_num_reading_elements;
         let offset_sensor_section = hwinfo_memory.dw_offset_of_sensor_section;
         let size_sensor_element = hwinfo_memory.dw_size_of_sensor_element;
-        let offset_reading_section = hwinfo_memory.dw_offset_of_reading_section;
-        let size_reading_section = hwinfo_memory.dw_size_of_reading_element;
 
         let mut master_sensor_names: Vec<String> = Vec::new();
         // #[allow(unused_mut)]
@@ -222,14 +252,14 @@ impl Hwinfo {
             UnmapViewOfFile(shared_memory_view);
         }
         Ok(Hwinfo {
-            num_reading_elements,
-            offset_reading_section,
-            size_reading_section,
             shared_memory_name,
             master_sensor_names: Box::new(master_sensor_names),
             master_label_user: Box::new(master_label_user),
             // master_readings: Box::new(master_readings),
             master_readings: Box::new(master_readings), // new_master_reading: Box::new(new_master_readings)
+            poll_time: 0,
+            previous_poll_time: 0,
+            previous_counters: HashMap::new(),
         })
     }
 
@@ -256,52 +286,84 @@ impl Hwinfo {
             )));
         }
         let start = shared_memory_view as *const u8;
-        // Getting Sensor Readings
-        for dw_reading in 0..self.num_reading_elements {
-            let offset = self.offset_reading_section + (dw_reading * self.size_reading_section);
-            let ptr = unsafe { start.offset(offset as isize) };
+        let header = unsafe {
+            std::slice::from_raw_parts(start, std::mem::size_of::<HwinfoSensorsSharedMem2>())
+                .align_to::<HwinfoSensorsSharedMem2>()
+                .1[0]
+        };
+        let size = header.dw_offset_of_reading_section as usize
+            + header.dw_num_reading_elements as usize * header.dw_size_of_reading_element as usize;
+        let result = self.read(unsafe { std::slice::from_raw_parts(start, size) });
+        unsafe {
+            // Unmap the shared memory view when done
+            UnmapViewOfFile(shared_memory_view);
+        }
+        result
+    }
 
-            let sensor_reading =
-                unsafe { std::slice::from_raw_parts(ptr, self.size_reading_section as usize) };
-            // if sensor_reading.len() != 460 {
-            //     panic!();
-            // }
+    /// Takes the readings from `shared_memory`, laid out the way HWiNFO shares them, from
+    /// its header on. `pull` reads them from HWiNFO itself.
+    pub fn read(&mut self, shared_memory: &[u8]) -> Result<(), anyhow::Error> {
+        let header_size = std::mem::size_of::<HwinfoSensorsSharedMem2>();
+        if shared_memory.len() < header_size {
+            return Err(anyhow::Error::new(Error::new(
+                ErrorKind::InvalidData,
+                "HWiNFO's shared memory is shorter than its header",
+            )));
+        }
+        let header = unsafe {
+            shared_memory[..header_size]
+                .align_to::<HwinfoSensorsSharedMem2>()
+                .1[0]
+        };
+        let poll_time = header.poll_time;
+        // HWiNFO polls at its own pace, pulling faster sees the same readings again
+        if poll_time != self.poll_time {
+            for ((sensor, reading), previous) in self.previous_counters.iter_mut() {
+                *previous = self
+                    .master_readings
+                    .sensors
+                    .get(sensor)
+                    .and_then(|current| current.reading.get(reading))
+                    .map(|current| current.value);
+            }
+            self.previous_poll_time = self.poll_time;
+            self.poll_time = poll_time;
+        }
+        let offset_reading_section = header.dw_offset_of_reading_section as usize;
+        let size_reading_section = header.dw_size_of_reading_element as usize;
+        if size_reading_section < std::mem::size_of::<HwinfoSensorsReadingElement>() {
+            return Err(anyhow::Error::new(Error::new(
+                ErrorKind::InvalidData,
+                "HWiNFO's readings are smaller than expected",
+            )));
+        }
+        // Getting Sensor Readings
+        for dw_reading in 0..header.dw_num_reading_elements as usize {
+            let offset = offset_reading_section + (dw_reading * size_reading_section);
+            let sensor_reading = shared_memory
+                .get(offset..offset + size_reading_section)
+                .ok_or_else(|| {
+                    anyhow::Error::new(Error::new(
+                        ErrorKind::InvalidData,
+                        "HWiNFO's readings run past its shared memory",
+                    ))
+                })?;
             let reading = unsafe { &sensor_reading.align_to::<HwinfoSensorsReadingElement>().1[0] };
             let label = String::from_utf8(reading.utf_label_user.to_vec())?
                 .trim_matches(char::from(0))
                 .to_string();
-            // self.master_label_user.insert(0, label);
-
-            // Because the packed struct is unaligned
-            // let value: f64 = reading.value;
-            // let value_min: f64 = reading.value_min;
-            // let value_max: f64 = reading.value_max;
-            // let value_avg: f64 = reading.value_avg;
-
-            // let mut values_list: [f64; 4] = [0.0_f64; 4];
-            // let unit = String::from_utf8(reading.utf_unit.to_vec())?
-            //     .trim_matches(char::from(0))
-            //     .to_string();
-            // // values_list.push(unit);
-            // values_list[0] = value;
-            // values_list[1] = value_min;
-            // values_list[2] = value_max;
-            // values_list[3] = value_avg;
-
-            let current_sensor_name = &self.master_sensor_names[reading.dw_sensor_index as usize];
-            // if let Some(x) = self.master_readings.get_mut(current_sensor_name) {
-            //     x.insert(label.clone(), (unit, values_list));
-            // }
 
+            let Some(current_sensor_name) = self
+                .master_sensor_names
+                .get(reading.dw_sensor_index as usize)
+            else {
+                continue;
+            };
             if let Some(sensor) = self.master_readings.sensors.get_mut(current_sensor_name) {
                 sensor.reading.insert(label, Box::new(reading.to_owned()));
             }
         }
-
-        unsafe {
-            // Unmap the shared memory view when done
-            UnmapViewOfFile(shared_memory_view);
-        }
         Ok(())
         // Ok(Hwinfo {
         //     num_reading_elements:   self.num_reading_elements,
@@ -314,6 +376,65 @@ impl Hwinfo {
         // })
     }
 
+    /// Readings given as `(sensor, reading, value, unit)` instead of read from HWiNFO's
+    /// shared memory, for tests. `pull` replaces them with the real ones.
+    pub fn from_readings(readings: &[(&str, &str, f64, &str)]) -> Hwinfo {
+        let mut master_sensor_names: Vec<String> = Vec::new();
+        let mut master_readings = MasterReadings {
+            sensors: Box::new(HashMap::new()),
+        };
+        for (sensor_name, label, value, unit) in readings {
+            if !master_sensor_names.contains(&sensor_name.to_string()) {
+                master_sensor_names.push(sensor_name.to_string());
+            }
+            let dw_sensor_index = master_sensor_names
+                .iter()
+                .position(|name| name == sensor_name)
+                .unwrap_or(0);
+            let sensor = master_readings
+                .sensors
+                .entry(sensor_name.to_string())
+                .or_insert_with(|| Sensor {
+                    sensor: Box::new(HwinfoSensorsSensorElement {
+                        dw_sensor_id: dw_sensor_index as u32,
+                        dw_sensor_inst: 0,
+                        sz_sensor_name_orig: fixed_string(sensor_name),
+                        sz_sensor_name_user: fixed_string(sensor_name),
+                        utf_sensor_name_user: fixed_string(sensor_name),
+                    }),
+                    reading: Box::new(HashMap::new()),
+                });
+            let reading = HwinfoSensorsReadingElement {
+                t_reading: SensorReadingType::SensorTypeNone,
+                _blank: [0; 3],
+                dw_sensor_index: dw_sensor_index as u32,
+                dw_reading_id: sensor.reading.len() as u32,
+                sz_label_orig: fixed_string(label),
+                sz_label_user: fixed_string(label),
+                sz_unit: fixed_string(unit),
+                value: *value,
+                value_min: *value,
+                value_max: *value,
+                value_avg: *value,
+                utf_label_user: fixed_string(label),
+                utf_unit: fixed_string(unit),
+            };
+            sensor.reading.insert(label.to_string(), Box::new(reading));
+        }
+        Hwinfo {
+            shared_memory_name: OsStr::new(HWINFO_SENSORS_MAP_FILE_NAME2)
+                .encode_wide()
+                .chain(once(0))
+                .collect::<Vec<u16>>(),
+            master_sensor_names: Box::new(master_sensor_names),
+            master_label_user: Box::new(Vec::new()),
+            master_readings: Box::new(master_readings),
+            poll_time: 0,
+            previous_poll_time: 0,
+            previous_counters: HashMap::new(),
+        }
+    }
+
     pub fn get(&self, sensor_key: &str, reading_key: &str) -> Option<&HwinfoSensorsReadingElement> {
         match self.master_readings.sensors.get(sensor_key) {
             Some(sensor) => match sensor.reading.get(reading_key) {
@@ -324,6 +445,30 @@ impl Hwinfo {
         }
     }
 
+    /// Keeps the value of `reading_key` at the previous HWiNFO poll from now on, for
+    /// [`Hwinfo::rate`].
+    pub fn track_counter(&mut self, sensor_key: &str, reading_key: &str) {
+        self.previous_counters
+            .entry((sensor_key.to_string(), reading_key.to_string()))
+            .or_insert(None);
+    }
+
+    /// Per-second change of a tracked cumulative reading, e.g. `Total DL`, between the last
+    /// two HWiNFO polls. `None` until there are two polls, or when the counter was reset.
+    pub fn rate(&self, sensor_key: &str, reading_key: &str, counter: &Counter) -> Option<f64> {
+        let previous = self
+            .previous_counters
+            .get(&(sensor_key.to_string(), reading_key.to_string()))
+            .copied()
+            .flatten()?;
+        if self.previous_poll_time == 0 {
+            return None;
+        }
+        let current = self.get(sensor_key, reading_key)?.value;
+        let seconds = (self.poll_time - self.previous_poll_time) as f64;
+        counter.rate(previous, current, seconds)
+    }
+
     pub fn find_first(&self, key: &str) -> Result<&HwinfoSensorsReadingElement, anyhow::Error> {
         for (_i, sensor) in self.master_readings.sensors.iter() {
             for (j, _reading) in sensor.reading.iter() {
@@ -365,3 +510,11 @@ impl Hwinfo {
 //         unsafe{ UnmapViewOfFile(self.shared_memory_name) };
 //     }
 // }
+
+/// `text` as a zero padded C string of `N` bytes, cut to fit.
+fn fixed_string<const N: usize>(text: &str) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let len = text.len().min(N - 1);
+    bytes[..len].copy_from_slice(&text.as_bytes()[..len]);
+    bytes
+}
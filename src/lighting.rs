@@ -0,0 +1,70 @@
+//! RGB and haptic feedback driven by the readings, next to the OLED pages.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    /// `255,0,0` or `#ff0000`.
+    pub fn parse(text: &str) -> Option<Rgb> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Rgb {
+                red: channel(0)?,
+                green: channel(2)?,
+                blue: channel(4)?,
+            });
+        }
+        let channels = text
+            .split(',')
+            .map(|channel| channel.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        match channels[..] {
+            [red, green, blue] => Some(Rgb { red, green, blue }),
+            _ => None,
+        }
+    }
+}
+
+/// GameSense `device-type` and `zone` a handler applies to, e.g. `mouse` and `logo`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Zone {
+    pub device_type: String,
+    pub zone: String,
+}
+
+/// What an alert does besides the OLED alert frame, per severity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertEffects {
+    pub color_zone: Zone,
+    pub warn_color: Option<Rgb>,
+    pub crit_color: Option<Rgb>,
+    /// Flashes per second of the alert color, 0 for a steady color.
+    pub flash: f64,
+    pub tactile_zone: Zone,
+    /// GameSense vibration patterns, e.g. `ti_predefined_strongclick_100`.
+    pub warn_tactile: Option<String>,
+    pub crit_tactile: Option<String>,
+}
+
+impl AlertEffects {
+    pub fn has_color(&self) -> bool {
+        self.warn_color.is_some() || self.crit_color.is_some()
+    }
+}
+
+/// Color of a zone following a reading, from `zero` at the bottom of its range to
+/// `hundred` at the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGradient {
+    pub zone: Zone,
+    pub zero: Rgb,
+    pub hundred: Rgb,
+}
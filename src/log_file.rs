@@ -0,0 +1,152 @@
+//! Append-only log files that rotate by size and age, for the history log.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_age: Option<Duration>,
+    /// Rotated files to keep, `history.csv.1` being the newest.
+    keep: usize,
+    file: Option<File>,
+    /// First line of the current file, the CSV column names.
+    header: Option<String>,
+    /// When the current file was started, for `max_age`.
+    started: Option<SystemTime>,
+}
+
+impl RotatingFile {
+    pub fn new(
+        path: PathBuf,
+        max_bytes: u64,
+        max_age: Option<Duration>,
+        keep: usize,
+    ) -> RotatingFile {
+        RotatingFile {
+            path,
+            max_bytes,
+            max_age,
+            keep,
+            file: None,
+            header: None,
+            started: None,
+        }
+    }
+
+    /// `path` with `.n` appended, where rotation moves the `n`th newest old file.
+    pub fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    pub fn append(&mut self, header: Option<&str>, line: &str) -> Result<(), anyhow::Error> {
+        self.append_at(SystemTime::now(), header, line)
+    }
+
+    /// Appends `line`, starting a new file with `header` when given. The current file is
+    /// rotated first once it is over the size or age limit, or when it starts with another
+    /// header, so old rows keep the columns they were written with.
+    pub fn append_at(
+        &mut self,
+        now: SystemTime,
+        header: Option<&str>,
+        line: &str,
+    ) -> Result<(), anyhow::Error> {
+        if self.file.is_none() {
+            self.open(now)?;
+        }
+        let size = self.size()?;
+        let too_old = match (self.max_age, self.started) {
+            (Some(max_age), Some(started)) => {
+                now.duration_since(started).is_ok_and(|age| age >= max_age)
+            }
+            _ => false,
+        };
+        let new_columns = header.is_some() && self.header.as_deref() != header;
+        if size > 0 && (size >= self.max_bytes || too_old || new_columns) {
+            self.rotate()?;
+            self.open(now)?;
+        }
+        if self.size()? == 0 {
+            if let Some(header) = header {
+                self.write_line(header)?;
+            }
+            self.header = header.map(String::from);
+            self.started = Some(now);
+        }
+        self.write_line(line)
+    }
+
+    fn open(&mut self, now: SystemTime) -> Result<(), anyhow::Error> {
+        // Carry on with a file left by an earlier run
+        self.header = None;
+        self.started = None;
+        if let Ok(existing) = File::open(&self.path) {
+            let metadata = existing.metadata()?;
+            let mut first_line = String::new();
+            BufReader::new(existing).read_line(&mut first_line)?;
+            self.header = Some(first_line.trim_end().to_string());
+            self.started = Some(
+                metadata
+                    .created()
+                    .or_else(|_| metadata.modified())
+                    .unwrap_or(now),
+            );
+        }
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?,
+        );
+        Ok(())
+    }
+
+    fn size(&self) -> Result<u64, anyhow::Error> {
+        match self.file.as_ref() {
+            Some(file) => Ok(file.metadata()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), anyhow::Error> {
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Shifts `history.csv` -> `history.csv.1` -> ..., dropping the oldest past `keep`.
+    fn rotate(&mut self) -> Result<(), anyhow::Error> {
+        self.file = None;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+            return Ok(());
+        }
+        fs::remove_file(self.rotated(self.keep)).ok();
+        for n in (1..self.keep).rev() {
+            fs::rename(self.rotated(n), self.rotated(n + 1)).ok();
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        Ok(())
+    }
+}
+
+/// Joins `fields` into a CSV line, quoting the ones with commas, quotes or line breaks.
+pub fn csv_row(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -2,39 +2,43 @@ mod settings;
 use ini::Ini;
 use settings::settings_create_config;
 
-mod consts;
-use consts::*;
-
 mod connect;
-use connect::{connect_hwinfo, connect_steelseries};
+use connect::{connect_hwinfo, connect_steelseries, game_metadata};
+
+mod history;
+use history::{HistoryConfig, HistoryLogger};
+
+mod mqtt;
+use mqtt::{MqttConfig, MqttPublisher};
 
 mod console_utils;
-use console_utils::{console_window, display_value_in_console, Console};
+use console_utils::{console_window, Console};
 
-mod steelseries;
-use steelseries::page_handler;
+mod shutdown;
 
-mod utils;
-use utils::{format_custom_value, run_sensors};
+mod tray;
+use tray::{Tray, TrayAction};
 
 use console::Term;
-use hwinfo_steelseries_oled::{Hwinfo, HwinfoSensorsReadingElement};
-use serde_json::json;
+use hwinfo_steelseries_oled::consts::*;
+use hwinfo_steelseries_oled::display::{
+    ConsoleSink, DisplaySink, FileSink, GameSenseSink, ScreenLayout,
+};
+use hwinfo_steelseries_oled::lighting::ColorGradient;
+use hwinfo_steelseries_oled::screen::Screen;
+use hwinfo_steelseries_oled::utils::{
+    alert_effects, lighting_fraction, lighting_gradients, page_readings, track_counters,
+};
+use hwinfo_steelseries_oled::Hwinfo;
 use std::num::Wrapping;
-use tray_icon::{Icon, TrayIconBuilder};
+use std::path::PathBuf;
+use std::time::Duration;
 
-#[allow(unreachable_code)]
 fn main() -> Result<(), anyhow::Error> {
-    let icon = Icon::from_path("assets/hwinfo-steelseries-icon.ico", Some((64, 64)))?;
-    let _tray = TrayIconBuilder::new()
-        .with_tooltip("HWiNFO-SteelSeries")
-        .with_icon(icon)
-        .build()?;
+    let tray = Tray::new()?;
 
     let term = Term::stdout();
 
-    let mut client = connect_steelseries(&term)?;
-
     let mut hwinfo = connect_hwinfo(&term)?;
     hwinfo.pull()?;
 
@@ -52,7 +56,6 @@ fn main() -> Result<(), anyhow::Error> {
             )))
         }
     };
-    // TODO: will error when using summary without a section for sensors
     // let config_sensors = match config.section(Some("PAGE1.Sensors")) {
     //     Some(sensors) => sensors,
     //     None => {
@@ -65,96 +68,123 @@ fn main() -> Result<(), anyhow::Error> {
     // std::thread::sleep(std::time::Duration::from_secs(1));
     // console_window(Console::HIDE);
 
-    let style = match config_main.get("style") {
-        Some(style) => style.to_lowercase(),
-        None => {
-            return Err(anyhow::Error::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Style not found",
-            )))
-        }
-    };
-    let vertical = match style.as_str() {
-        "vertical" => Some(true),
-        "horizontal" => Some(false),
-        _ => None,
-    };
-    let summary = match style.as_str() {
-        "vertical" | "horizontal" => true,
-        _ => false,
+    let mut screens = Screen::from_ini(&config_file)?;
+    let lighting = config_file.section(Some("Lighting"));
+    let gradients = match lighting {
+        Some(lighting) => lighting_gradients(lighting)?,
+        None => Vec::new(),
     };
 
-    let mut gpu: &str = "";
-    if summary {
-        gpu = match config_main.get("gpu") {
-            Some(gpu) => gpu,
-            None => "",
-        };
+    #[cfg(debug_assertions)]
+    let default_outputs = "gamesense,console";
+    #[cfg(not(debug_assertions))]
+    let default_outputs = "gamesense";
+    let mut sinks: Vec<Box<dyn DisplaySink>> = Vec::new();
+    for output in config_main
+        .get("outputs")
+        .unwrap_or(default_outputs)
+        .split(",")
+        .map(|output| output.trim().to_lowercase())
+    {
+        match output.as_str() {
+            "gamesense" => {
+                let client = connect_steelseries(&term, game_metadata(&config_file)?)?;
+                let layouts = screens
+                    .iter()
+                    .map(|screen| screen.layout())
+                    .collect::<Vec<ScreenLayout>>();
+                let mut sink = GameSenseSink::new(client, &layouts)?;
+                if let Some(effects) = alert_effects(&config_file)? {
+                    sink.bind_alert_effects(effects)?;
+                }
+                for (k, gradient) in gradients.iter() {
+                    sink.bind_gradient(*k, gradient)?;
+                }
+                sinks.push(Box::new(sink));
+            }
+            "console" => sinks.push(Box::new(ConsoleSink::new(term.clone()))),
+            "file" => {
+                let path = config_main.get("output_file").unwrap_or("frame.txt");
+                sinks.push(Box::new(FileSink::new(PathBuf::from(path))));
+            }
+            "" => continue,
+            _ => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unknown output: {}", output),
+                )))
+            }
+        }
     }
 
-    let decimal = match config_main.get("decimal") {
-        Some(decimal) => decimal.parse::<bool>()?,
-        None => false,
-    };
+    let mut mqtt = MqttConfig::from_ini(&config_file)?.map(MqttPublisher::new);
+    let all_pages = screens
+        .iter()
+        .flat_map(|screen| screen.pages.iter().copied())
+        .collect::<Vec<&ini::Properties>>();
+    let selected_readings = page_readings(&all_pages);
+    // [Lighting] reads its sensors like a page, `rate_N` included
+    let mut counted = all_pages.clone();
+    counted.extend(lighting);
+    track_counters(&counted, &mut hwinfo)?;
 
-    #[cfg(debug_assertions)]
-    let display_in_console = true;
-    #[cfg(not(debug_assertions))]
-    let display_in_console = false;
+    let mut history = HistoryConfig::from_ini(&config_file)?.map(HistoryLogger::new);
 
-    let pages = match config_main.get("pages") {
-        Some(pages) => pages.parse::<usize>()?,
-        None => 1,
-    };
-    let mut pages_vec = Vec::new();
-    for i in 1..=pages {
-        match config_file.section(Some(format!("PAGE{}.Sensors", i))) {
-            Some(page) => {
-                // client.register_event(format!("PAGE{}", i).as_str())?;
-                let handler = page_handler(3, "line1", "line2", "line3", None);
-                client.bind_event(
-                    format!("PAGE{}", i).as_str(),
-                    None,
-                    None,
-                    None,
-                    None,
-                    vec![handler],
-                )?;
-                pages_vec.push(page);
-            }
-            None => continue,
-        };
+    shutdown::install_console_handler()?;
+    let result = run(
+        &tray,
+        &term,
+        &mut hwinfo,
+        &mut screens,
+        &mut sinks,
+        &mut mqtt,
+        &mut history,
+        lighting,
+        &gradients,
+        &selected_readings,
+    );
+
+    // Even after an error, so GG doesn't keep the last readings on screen
+    term.write_line("Shutting down").ok();
+    for sink in sinks.iter_mut() {
+        if let Err(err) = sink.shutdown() {
+            term.write_line(format!("{}", err).as_str()).ok();
+        }
     }
+    if let Some(mqtt) = mqtt.as_mut() {
+        mqtt.disconnect();
+    }
+    shutdown::finished();
+
+    result
+}
 
-    client.start_heartbeat();
+/// Refreshes the readings and every output once per tick until a shutdown is requested.
+#[allow(clippy::too_many_arguments)]
+fn run(
+    tray: &Tray,
+    term: &Term,
+    hwinfo: &mut Hwinfo,
+    screens: &mut [Screen],
+    sinks: &mut [Box<dyn DisplaySink>],
+    mqtt: &mut Option<MqttPublisher>,
+    history: &mut Option<HistoryLogger>,
+    lighting: Option<&ini::Properties>,
+    gradients: &[(usize, ColorGradient)],
+    selected_readings: &[(String, String)],
+) -> Result<(), anyhow::Error> {
     let mut i = Wrapping(0isize);
     let mut count: usize = 0;
-    let mut page_counter: usize = 0;
-    let page_time = match config_main.get("page_time") {
-        Some(second) => {
-            let num = second.parse::<isize>()?;
-            match num {
-                0..=60 => num,
-                _ => 5,
-            }
-        }
-        None => 5,
-    };
-    loop {
+    while shutdown::running() {
         // Logic to alternate between pages
-        if i.0 % page_time == 0 && i.0 != 0 {
-            if page_counter >= pages - 1 {
-                page_counter = 0;
-            } else {
-                page_counter += 1;
-            }
+        for screen in screens.iter_mut() {
+            screen.rotate(i.0);
         }
-        let pages_sensors = pages_vec[page_counter];
 
         let limit = 5;
         let old = hwinfo.clone();
         hwinfo.pull()?;
-        if old == hwinfo {
+        if old == *hwinfo {
             if count < limit {
                 count += 1;
             }
@@ -163,156 +193,74 @@ fn main() -> Result<(), anyhow::Error> {
             // console_window(Console::HIDE);
         }
         drop(old);
-        #[allow(unused_assignments)]
-        let mut value = json!("");
         if count >= limit {
             console_window(Console::SHOW);
             term.clear_line()?;
             term.write_line("Disconnected from HWiNFO")?;
-            value = json!({"line1":"Disconnected",
-                           "line2":"FROM",
-                           "line3":"HWiNFO"});
-            client.trigger_event_frame("ERROR", i.0, value)?;
+            for screen in screens.iter() {
+                let frame = screen.status(vec![
+                    String::from("Disconnected"),
+                    String::from("FROM"),
+                    String::from("HWiNFO"),
+                ]);
+                for sink in sinks.iter_mut() {
+                    sink.show(&frame)?;
+                }
+            }
             i += 1;
-            std::thread::sleep(std::time::Duration::from_millis(TICK_RATE));
+            wait_tick(tray, screens);
             continue;
         }
 
-        if summary {
-            let mut labels: Vec<&str> = vec!["CPU", "", "GPU", "", "MEM", ""];
-            let mut units: Vec<&str> = vec!["°", "%", "°", "%", "MB", "MB"];
-            let mut values: Vec<String> = vec![String::new(); CUSTOM_SENSORS];
-            let sensors_per_line: u8 = 2;
-
-            let sensor_cpu_usage = hwinfo.find_first("Total CPU Usage")?;
-            let sensor_cpu_temp = hwinfo.find_first("CPU (Tctl/Tdie)")?;
+        if let Some(mqtt) = mqtt.as_mut() {
+            if let Err(err) = mqtt.publish(hwinfo, selected_readings) {
+                term.write_line(format!("MQTT: {}", err).as_str())?;
+            }
+        }
 
-            let sensor_gpu_usage = hwinfo.find_first("GPU Core Load")?;
-            let sensor_gpu_temp: &HwinfoSensorsReadingElement;
-            if gpu == "" {
-                sensor_gpu_temp = hwinfo.find_first("GPU Temperature")?;
-            } else {
-                sensor_gpu_temp = match hwinfo.get(gpu, "GPU Temperature") {
-                    Some(sensor) => sensor,
-                    None => {
-                        return Err(anyhow::Error::new(std::io::Error::new(
-                            std::io::ErrorKind::NotFound,
-                            "GPU Temperature not found",
-                        )))
+        if let Some(lighting) = lighting {
+            for (k, _) in gradients.iter() {
+                if let Some(fraction) = lighting_fraction(lighting, *k, hwinfo)? {
+                    for sink in sinks.iter_mut() {
+                        sink.gradient(*k, fraction)?;
                     }
-                };
+                }
             }
+        }
 
-            let sensor_mem_used = hwinfo.find_first("Physical Memory Used")?;
-            let sensor_mem_free = hwinfo.find_first("Physical Memory Available")?;
-            let sensor_mem_load = hwinfo.find_first("Physical Memory Load")?;
-            let cpu_temp_cur_value = sensor_cpu_temp.value;
-            let cpu_usage_cur_value = sensor_cpu_usage.value;
-            let temp_unit = "°"; 
-            let usage_unit = "%";
-            let gpu_temp_cur_value = sensor_gpu_temp.value;
-            let gpu_usage_cur_value = sensor_gpu_usage.value;
-            let mem_unit = "G";
-            let mem_used = sensor_mem_used.value / 1024.0;
-            let mem_free = sensor_mem_free.value / 1024.0;
-            let mem_load = sensor_mem_load.value;
-            let line1_spaces = " ";
-            let line2_spaces = " ";
-
-            if vertical.unwrap_or(true) {
-                if decimal {
-                    value = json!({
-                        "line1": "CPU   GPU   MEM",
-                        "line2": format!("{:.1}{}{}{:.1}{}{}{:.1}{}",
-                            cpu_temp_cur_value, temp_unit,
-                            line1_spaces,
-                            gpu_temp_cur_value, temp_unit,
-                            line1_spaces,
-                            mem_used, mem_unit),
-                        "line3": format!("{:.1}{}{}{:.1}{}{}{:.1}{}",
-                            cpu_usage_cur_value, usage_unit,
-                            line2_spaces,
-                            gpu_usage_cur_value, usage_unit,
-                            line2_spaces,
-                            mem_free, mem_unit),
-                    });
-                } else {
-                    value = json!({
-                        "line1": "CPU   GPU   MEM",
-                        "line2": format!("{:.0}{}{}{:.0}{}{}{:.0}{}",
-                            cpu_temp_cur_value, temp_unit,
-                            "   ",
-                            gpu_temp_cur_value, temp_unit,
-                            "   ",
-                            mem_used, mem_unit),
-                        "line3": format!("{:.0}{}{}{:.0}{}{}{:.0}{}",
-                            cpu_usage_cur_value, usage_unit,
-                            "    ",
-                            gpu_usage_cur_value, usage_unit,
-                            "    ",
-                            mem_free, mem_unit),
-                    });
-                }
-            } else {
-                // Horizontal
-                if decimal {
-                    value = json!({
-                        "line1": format!("CPU {:.1}{} {:.1}{}",
-                            cpu_temp_cur_value, temp_unit,
-                            cpu_usage_cur_value, usage_unit),
-                        "line2": format!("GPU {:.1}{} {:.1}{}",
-                            gpu_temp_cur_value, temp_unit,
-                            gpu_usage_cur_value, usage_unit),
-                        "line3": format!("MEM {:.1}{} {:.1}{}",
-                            mem_used, mem_unit,
-                            mem_load, usage_unit,
-                            // mem_free, mem_unit.to_lowercase()
-                        ),
-                    });
-                } else {
-                    value = json!({
-                        "line1": format!("CPU {:.0}{} {:.0}{}",
-                            cpu_temp_cur_value, temp_unit,
-                            cpu_usage_cur_value, usage_unit),
-                        "line2": format!("GPU {:.0}{} {:.0}{}",
-                            gpu_temp_cur_value, temp_unit,
-                            gpu_usage_cur_value, usage_unit),
-                        "line3": format!("MEM {:.0}{} {:.0}{}",
-                            mem_used, mem_unit,
-                            mem_load, usage_unit,
-                            // mem_free, mem_unit.to_lowercase()
-                        ),
-                    });
+        for screen in screens.iter_mut() {
+            screen.sample(hwinfo);
+            let frame = screen.frame(hwinfo)?;
+            // The log follows the first screen
+            if let Some(history) = history.as_mut().filter(|_| screen.index == 0) {
+                if let Err(err) = history.log(&frame, hwinfo, selected_readings) {
+                    term.write_line(format!("History: {}", err).as_str())?;
                 }
             }
-        } else {
-            // Custom Senors
-            let mut labels = vec![""; CUSTOM_SENSORS];
-            let mut units = vec![""; CUSTOM_SENSORS];
-            let mut values = vec![String::new(); CUSTOM_SENSORS];
-
-            let sensors_per_line = match config_main.get("sensors_per_line") {
-                Some(spl) => spl.parse::<u8>()?,
-                None => 1,
-            };
-            run_sensors(
-                pages_sensors,
-                &mut labels,
-                &mut units,
-                &mut values,
-                &hwinfo,
-                decimal,
-            )?;
-            value = format_custom_value(sensors_per_line, labels, values, units);
+            for sink in sinks.iter_mut() {
+                sink.show(&frame)?;
+            }
         }
-        if display_in_console {
-            display_value_in_console(&term, &value)?;
+        let severity = screens.iter().filter_map(|screen| screen.severity()).max();
+        for sink in sinks.iter_mut() {
+            sink.alert(severity)?;
         }
-        client.trigger_event_frame(format!("PAGE{}", page_counter + 1).as_str(), i.0, value)?;
         i += 1;
-        std::thread::sleep(std::time::Duration::from_millis(TICK_RATE));
+        wait_tick(tray, screens);
     }
-    client.stop_heartbeat()?;
 
     Ok(())
 }
+
+fn wait_tick(tray: &Tray, screens: &mut [Screen]) {
+    for action in tray.wait(Duration::from_millis(TICK_RATE)) {
+        match action {
+            TrayAction::AcknowledgeAlerts => {
+                for screen in screens.iter_mut() {
+                    screen.acknowledge_alerts();
+                }
+            }
+            TrayAction::Quit => shutdown::request(),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use hwinfo_steelseries_oled::mqtt_client::{MqttClient, MqttLogin};
+use hwinfo_steelseries_oled::{Hwinfo, HwinfoSensorsReadingElement, SensorReadingType};
+use serde_json::json;
+
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic: String,
+    pub all_readings: bool,
+    pub discovery: bool,
+    pub discovery_prefix: String,
+    pub keep_alive: u16,
+}
+
+impl MqttConfig {
+    /// Reads the optional `[MQTT]` section. Returns `None` when the section is missing
+    /// or `enabled=false`.
+    pub fn from_ini(config_file: &ini::Ini) -> Result<Option<MqttConfig>, anyhow::Error> {
+        let section = match config_file.section(Some("MQTT")) {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let enabled = match section.get("enabled") {
+            Some(enabled) => enabled.parse::<bool>()?,
+            None => true,
+        };
+        if !enabled {
+            return Ok(None);
+        }
+        let all_readings = match section.get("readings") {
+            Some(readings) => match readings.to_lowercase().as_str() {
+                "all" => true,
+                "pages" => false,
+                _ => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("MQTT readings must be \"pages\" or \"all\", got \"{}\"", readings),
+                    )))
+                }
+            },
+            None => false,
+        };
+        Ok(Some(MqttConfig {
+            host: section.get("host").unwrap_or("127.0.0.1").to_string(),
+            port: match section.get("port") {
+                Some(port) => port.parse::<u16>()?,
+                None => 1883,
+            },
+            client_id: section
+                .get("client_id")
+                .unwrap_or("hwinfo-steelseries")
+                .to_string(),
+            username: section.get("username").map(|s| s.to_string()),
+            password: section.get("password").map(|s| s.to_string()),
+            topic: section
+                .get("topic")
+                .unwrap_or("hwinfo")
+                .trim_end_matches('/')
+                .to_string(),
+            all_readings,
+            discovery: match section.get("discovery") {
+                Some(discovery) => discovery.parse::<bool>()?,
+                None => false,
+            },
+            discovery_prefix: section
+                .get("discovery_prefix")
+                .unwrap_or("homeassistant")
+                .trim_end_matches('/')
+                .to_string(),
+            keep_alive: match section.get("keep_alive") {
+                Some(keep_alive) => keep_alive.parse::<u16>()?,
+                None => 60,
+            },
+        }))
+    }
+}
+
+/// Publishes the readings to an MQTT broker, reconnecting when it goes away.
+pub struct MqttPublisher {
+    config: MqttConfig,
+    client: Option<MqttClient>,
+    last_packet: Instant,
+    retry_at: Option<Instant>,
+    discovered: HashSet<String>,
+}
+
+impl MqttPublisher {
+    pub fn new(config: MqttConfig) -> MqttPublisher {
+        MqttPublisher {
+            config,
+            client: None,
+            last_packet: Instant::now(),
+            retry_at: None,
+            discovered: HashSet::new(),
+        }
+    }
+
+    /// Publishes the selected readings. On failure the connection is dropped and
+    /// retried on a later tick, so the display keeps running without a broker.
+    pub fn publish(
+        &mut self,
+        hwinfo: &Hwinfo,
+        selected: &[(String, String)],
+    ) -> Result<(), anyhow::Error> {
+        if let Some(retry_at) = self.retry_at {
+            if Instant::now() < retry_at {
+                return Ok(());
+            }
+        }
+        match self.publish_inner(hwinfo, selected) {
+            Ok(()) => {
+                self.retry_at = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.client = None;
+                self.discovered.clear();
+                self.retry_at = Some(Instant::now() + RETRY_DELAY);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.disconnect();
+        }
+    }
+
+    fn publish_inner(
+        &mut self,
+        hwinfo: &Hwinfo,
+        selected: &[(String, String)],
+    ) -> Result<(), anyhow::Error> {
+        if self.client.is_none() {
+            self.connect()?;
+        }
+        let mut published = false;
+        if self.config.all_readings || selected.is_empty() {
+            for (sensor_name, sensor) in hwinfo.master_readings.sensors.iter() {
+                for (reading_name, reading) in sensor.reading.iter() {
+                    self.publish_reading(sensor_name, reading_name, reading)?;
+                    published = true;
+                }
+            }
+        } else {
+            for (sensor_name, reading_name) in selected {
+                if let Some(reading) = hwinfo.get(sensor_name, reading_name) {
+                    self.publish_reading(sensor_name, reading_name, reading)?;
+                    published = true;
+                }
+            }
+        }
+        if !published && self.last_packet.elapsed().as_secs() >= (self.config.keep_alive / 2) as u64
+        {
+            self.client()?.ping()?;
+            self.last_packet = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn publish_reading(
+        &mut self,
+        sensor_name: &str,
+        reading_name: &str,
+        reading: &HwinfoSensorsReadingElement,
+    ) -> Result<(), anyhow::Error> {
+        let object_id = format!("{}_{}", slug(sensor_name), slug(reading_name));
+        let state_topic = format!(
+            "{}/{}/{}",
+            self.config.topic,
+            slug(sensor_name),
+            slug(reading_name)
+        );
+        if self.config.discovery && !self.discovered.contains(&object_id) {
+            let node_id = slug(&self.config.client_id);
+            let mut config = json!({
+                "name": format!("{} {}", sensor_name, reading_name),
+                "state_topic": state_topic,
+                "unique_id": format!("{}_{}", node_id, object_id),
+                "state_class": "measurement",
+                "device": {
+                    "identifiers": [node_id],
+                    "name": "HWiNFO",
+                    "manufacturer": "HWiNFO-SteelSeries",
+                },
+            });
+            let unit = reading.unit();
+            if !unit.is_empty() {
+                config["unit_of_measurement"] = json!(unit);
+            }
+            if let Some(device_class) = device_class(reading.t_reading) {
+                config["device_class"] = json!(device_class);
+            }
+            let config_topic = format!(
+                "{}/sensor/{}/{}/config",
+                self.config.discovery_prefix, node_id, object_id
+            );
+            self.publish_message(&config_topic, config.to_string().as_bytes(), true)?;
+            self.discovered.insert(object_id);
+        }
+        let value = reading.value;
+        self.publish_message(&state_topic, format!("{:.3}", value).as_bytes(), false)
+    }
+
+    fn connect(&mut self) -> Result<(), anyhow::Error> {
+        let login = MqttLogin {
+            client_id: &self.config.client_id,
+            username: self.config.username.as_deref(),
+            password: self.config.password.as_deref(),
+            keep_alive: self.config.keep_alive,
+        };
+        self.client = Some(MqttClient::connect(
+            &self.config.host,
+            self.config.port,
+            &login,
+        )?);
+        self.last_packet = Instant::now();
+        Ok(())
+    }
+
+    fn publish_message(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        retain: bool,
+    ) -> Result<(), anyhow::Error> {
+        self.client()?.publish(topic, payload, retain)?;
+        self.last_packet = Instant::now();
+        Ok(())
+    }
+
+    fn client(&mut self) -> Result<&mut MqttClient, anyhow::Error> {
+        match self.client.as_mut() {
+            Some(client) => Ok(client),
+            None => Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "MQTT not connected",
+            ))),
+        }
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+fn device_class(reading_type: SensorReadingType) -> Option<&'static str> {
+    match reading_type {
+        SensorReadingType::SensorTypeTemp => Some("temperature"),
+        SensorReadingType::SensorTypeVolt => Some("voltage"),
+        SensorReadingType::SensorTypeCurrent => Some("current"),
+        SensorReadingType::SensorTypePower => Some("power"),
+        SensorReadingType::SensorTypeClock => Some("frequency"),
+        _ => None,
+    }
+}
+
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    slug.trim_matches('_').to_string()
+}
@@ -0,0 +1,139 @@
+//! Minimal MQTT 3.1.1 client (QoS 0 only), enough to feed a broker such as mosquitto.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::bail;
+
+pub const PINGREQ: [u8; 2] = [0xC0, 0x00];
+pub const DISCONNECT: [u8; 2] = [0xE0, 0x00];
+
+/// For connecting, and for every read and write after.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct MqttLogin<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    /// Seconds the broker waits for a packet before dropping the client.
+    pub keep_alive: u16,
+}
+
+pub struct MqttClient {
+    stream: TcpStream,
+}
+
+impl MqttClient {
+    /// Connects and waits for the broker to accept the CONNECT. Gives up after a few
+    /// seconds, as the render loop waits meanwhile.
+    pub fn connect(host: &str, port: u16, login: &MqttLogin) -> Result<MqttClient, anyhow::Error> {
+        let mut stream = connect_timeout(host, port)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        stream.write_all(&connect_packet(login))?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::ConnectionRefused,
+                format!("MQTT broker refused connection (code {})", connack[3]),
+            )));
+        }
+        Ok(MqttClient { stream })
+    }
+
+    pub fn publish(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        retain: bool,
+    ) -> Result<(), anyhow::Error> {
+        self.send(&publish_packet(topic, payload, retain)?)
+    }
+
+    pub fn ping(&mut self) -> Result<(), anyhow::Error> {
+        self.send(&PINGREQ)
+    }
+
+    pub fn disconnect(mut self) {
+        self.send(&DISCONNECT).ok();
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> Result<(), anyhow::Error> {
+        self.stream.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+/// First address of `host` that accepts a connection within [`TIMEOUT`].
+fn connect_timeout(host: &str, port: u16) -> Result<TcpStream, anyhow::Error> {
+    let mut last_error = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    match last_error {
+        Some(err) => Err(anyhow::Error::new(err)),
+        None => bail!("MQTT broker {} has no address", host),
+    }
+}
+
+pub fn connect_packet(login: &MqttLogin) -> Vec<u8> {
+    let mut flags = 0x02; // Clean session
+    let mut payload = Vec::new();
+    write_string(&mut payload, login.client_id);
+    if let Some(username) = login.username {
+        flags |= 0x80;
+        write_string(&mut payload, username);
+    }
+    if let Some(password) = login.password {
+        flags |= 0x40;
+        write_string(&mut payload, password);
+    }
+    let mut body = Vec::new();
+    write_string(&mut body, "MQTT");
+    body.push(4); // Protocol level 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&login.keep_alive.to_be_bytes());
+    body.extend_from_slice(&payload);
+    packet(0x10, &body)
+}
+
+pub fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Result<Vec<u8>, anyhow::Error> {
+    if topic.len() > u16::MAX as usize {
+        bail!("MQTT topic is too long: {}", topic);
+    }
+    let mut body = Vec::new();
+    write_string(&mut body, topic);
+    body.extend_from_slice(payload);
+    let header = if retain { 0x31 } else { 0x30 };
+    Ok(packet(header, &body))
+}
+
+/// Fixed header, the body length as a varint of 7 bits per byte, then the body.
+pub fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
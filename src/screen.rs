@@ -0,0 +1,418 @@
+use crate::alert::{alert_lines, Alert, AlertSettings, AlertState, Severity, Thresholds};
+use crate::consts::CUSTOM_SENSORS;
+use crate::device::{DeviceProfile, DEFAULT_PROFILE, PROFILES};
+use crate::display::{Frame, LineKind, PageRender, ScreenLayout};
+use crate::filter::FilterState;
+use crate::sparkline::Sparkline;
+use crate::text::marquee;
+use crate::utils::{
+    alert_settings, conversion, fit_lines, format_custom_value, format_summary_value,
+    line_bar_values, line_bars, line_marquee, page_alerts, page_filters, page_sparklines,
+    page_thresholds, render_bitmap_page, run_sensors, sample_filters, sample_sparklines,
+    sensor_counter, sensor_format, SummaryFormat,
+};
+use crate::Hwinfo;
+use ini::Ini;
+
+/// Everything needed to render the pages of one screen.
+///
+/// The first screen is configured by `[Main]` and uses `[PAGE1.Sensors]` onwards.
+/// `[Screen2]`, `[Screen3]`... take the same keys plus `first_page`, the number of their
+/// first `[PAGEn.Sensors]` section.
+pub struct Screen<'a> {
+    pub index: usize,
+    pub device: DeviceProfile,
+    summary: bool,
+    vertical: Option<bool>,
+    gpu: &'a str,
+    summary_format: SummaryFormat,
+    decimal: bool,
+    sensors_per_line: u8,
+    pub pages: Vec<&'a ini::Properties>,
+    renders: Vec<PageRender>,
+    bars: Vec<Vec<Option<usize>>>,
+    marquees: Vec<Vec<Option<usize>>>,
+    sparklines: Vec<Vec<Option<Sparkline>>>,
+    /// Smoothing of `filter_k`, per page and sensor.
+    filters: Vec<Vec<Option<FilterState>>>,
+    thresholds: Vec<Vec<Thresholds>>,
+    alert_states: Vec<Vec<AlertState>>,
+    alert_settings: AlertSettings,
+    /// Alerts to show, from the last [`Screen::sample`].
+    alerts: Vec<Alert>,
+    page_time: isize,
+    page_counter: usize,
+    /// Ticks since the current page came up, drives the marquee.
+    page_ticks: usize,
+}
+
+impl<'a> Screen<'a> {
+    /// `[Main]` followed by every `[ScreenN]` section, numbered from 2 without gaps.
+    pub fn from_ini(config_file: &'a Ini) -> Result<Vec<Screen<'a>>, anyhow::Error> {
+        let config_main = match config_file.section(Some("Main")) {
+            Some(main) => main,
+            None => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Config Not found",
+                )))
+            }
+        };
+        let mut screens = vec![Screen::new(config_file, config_main, config_main, 0)?];
+        while let Some(section) = config_file.section(Some(format!("Screen{}", screens.len() + 1)))
+        {
+            let index = screens.len();
+            screens.push(Screen::new(config_file, config_main, section, index)?);
+        }
+        Ok(screens)
+    }
+
+    fn new(
+        config_file: &'a Ini,
+        config_main: &'a ini::Properties,
+        section: &'a ini::Properties,
+        index: usize,
+    ) -> Result<Screen<'a>, anyhow::Error> {
+        let name = match index {
+            0 => String::from("Main"),
+            index => format!("Screen{}", index + 1),
+        };
+        let style = match section.get("style") {
+            Some(style) => style.to_lowercase(),
+            None => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Style not found in [{}]", name),
+                )))
+            }
+        };
+        let vertical = match style.as_str() {
+            "vertical" => Some(true),
+            "horizontal" => Some(false),
+            _ => None,
+        };
+        let summary = vertical.is_some();
+
+        let mut gpu: &str = "";
+        if summary {
+            gpu = section.get("gpu").unwrap_or("");
+        }
+
+        // Shared with the first screen unless overridden
+        let decimal = match section.get("decimal").or(config_main.get("decimal")) {
+            Some(decimal) => decimal.parse::<bool>()?,
+            None => false,
+        };
+        let summary_format = SummaryFormat::from_section(section, config_main, decimal)?;
+
+        let first_page = match section.get("first_page") {
+            Some(first_page) => first_page.parse::<usize>()?,
+            None => 1,
+        };
+        let page_total = match section.get("pages") {
+            Some(pages) => pages.parse::<usize>()?,
+            None => 1,
+        };
+        let mut pages = Vec::new();
+        for i in first_page..first_page + page_total {
+            match config_file.section(Some(format!("PAGE{}.Sensors", i))) {
+                Some(page) => pages.push(page),
+                None => continue,
+            };
+        }
+        // Summary styles don't need a sensors section but still show as PAGE1
+        let page_count = if summary { 1 } else { pages.len() };
+        if page_count == 0 {
+            return Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Sensors Config Not found for [{}]", name),
+            )));
+        }
+
+        let device_name = section.get("device").unwrap_or(DEFAULT_PROFILE);
+        let device = match DeviceProfile::from_name(device_name) {
+            Some(device) => device,
+            None => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown device: {}, expected one of {}",
+                        device_name,
+                        PROFILES
+                            .iter()
+                            .map(|profile| profile.name)
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    ),
+                )))
+            }
+        };
+
+        let mut renders = Vec::new();
+        let mut bars = Vec::new();
+        for page in 0..page_count {
+            let page_bars = match pages.get(page) {
+                Some(pages_sensors) if !summary => line_bars(pages_sensors, device.lines)?,
+                _ => vec![None; device.lines],
+            };
+            let render = match pages.get(page).and_then(|page| page.get("render")) {
+                Some(render) => render.to_lowercase(),
+                None => String::from("text"),
+            };
+            renders.push(match render.as_str() {
+                "text" => PageRender::Text {
+                    lines: page_bars
+                        .iter()
+                        .map(|bar| match bar {
+                            Some(_) => LineKind::ProgressBar,
+                            None => LineKind::Text,
+                        })
+                        .collect(),
+                },
+                // Images only show on a screen of exactly their size
+                "bitmap" if device.name == DEFAULT_PROFILE => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "render=bitmap for PAGE{} needs the size of the screen, set device in [{}] to one of {}",
+                            first_page + page,
+                            name,
+                            PROFILES
+                                .iter()
+                                .filter(|profile| profile.name != DEFAULT_PROFILE)
+                                .map(|profile| profile.name)
+                                .collect::<Vec<&str>>()
+                                .join(", ")
+                        ),
+                    )))
+                }
+                "bitmap" => PageRender::Bitmap {
+                    width: device.width,
+                    height: device.height,
+                },
+                _ => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Unknown render for PAGE{}: {}", first_page + page, render),
+                    )))
+                }
+            });
+            bars.push(page_bars);
+        }
+        let mut sparklines = Vec::new();
+        let mut filters = Vec::new();
+        let mut marquees = Vec::new();
+        let mut thresholds = Vec::new();
+        for page in pages.iter() {
+            // Typos in convert_k, format_k and rate_k show up at startup rather than on the page
+            for k in 0..CUSTOM_SENSORS {
+                conversion(page, k)?;
+                sensor_format(page, k, decimal)?;
+                sensor_counter(page, k)?;
+            }
+            sparklines.push(page_sparklines(page)?);
+            filters.push(page_filters(page)?);
+            thresholds.push(page_thresholds(page)?);
+            let mut page_marquees = Vec::new();
+            for line in 1..=device.lines {
+                page_marquees.push(line_marquee(page, line)?);
+            }
+            marquees.push(page_marquees);
+        }
+
+        let sensors_per_line = match section.get("sensors_per_line") {
+            Some(spl) => spl.parse::<u8>()?,
+            None => 1,
+        };
+        // More would only add empty cells, and bitmap cells narrower than a pixel
+        if sensors_per_line as usize > CUSTOM_SENSORS {
+            return Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "sensors_per_line in [{}] can't be more than {}",
+                    name, CUSTOM_SENSORS
+                ),
+            )));
+        }
+        let page_time = match section.get("page_time") {
+            Some(second) => {
+                let num = second.parse::<isize>()?;
+                match num {
+                    0..=60 => num,
+                    _ => 5,
+                }
+            }
+            None => 5,
+        };
+
+        Ok(Screen {
+            index,
+            device,
+            summary,
+            vertical,
+            gpu,
+            summary_format,
+            decimal,
+            sensors_per_line,
+            pages,
+            renders,
+            bars,
+            marquees,
+            sparklines,
+            filters,
+            alert_states: vec![vec![AlertState::default(); CUSTOM_SENSORS]; thresholds.len()],
+            thresholds,
+            alert_settings: alert_settings(config_file)?,
+            alerts: Vec::new(),
+            page_time,
+            page_counter: 0,
+            page_ticks: 0,
+        })
+    }
+
+    pub fn layout(&self) -> ScreenLayout {
+        ScreenLayout {
+            device: self.device,
+            pages: self.renders.clone(),
+        }
+    }
+
+    /// Moves to the next page every `page_time` ticks. `page_time=0` stays on the first page.
+    pub fn rotate(&mut self, tick: isize) {
+        if tick == 0 {
+            return;
+        }
+        if self.page_time != 0 && tick % self.page_time == 0 {
+            if self.page_counter >= self.renders.len() - 1 {
+                self.page_counter = 0;
+            } else {
+                self.page_counter += 1;
+            }
+            self.page_ticks = 0;
+        } else {
+            self.page_ticks += 1;
+        }
+    }
+
+    /// Records the graphs, feeds the filters and updates the alerts of every page, once per
+    /// tick.
+    pub fn sample(&mut self, hwinfo: &Hwinfo) {
+        for (page, sparklines) in self.pages.iter().zip(self.sparklines.iter_mut()) {
+            sample_sparklines(page, sparklines, hwinfo);
+        }
+        for (page, filters) in self.pages.iter().zip(self.filters.iter_mut()) {
+            sample_filters(page, filters, hwinfo);
+        }
+        // A sensor on several pages is reported once
+        self.alerts.clear();
+        for ((page, thresholds), states) in self
+            .pages
+            .iter()
+            .zip(self.thresholds.iter())
+            .zip(self.alert_states.iter_mut())
+        {
+            for alert in page_alerts(
+                page,
+                thresholds,
+                states,
+                &self.alert_settings,
+                hwinfo,
+                self.decimal,
+            ) {
+                if !self.alerts.contains(&alert) {
+                    self.alerts.push(alert);
+                }
+            }
+        }
+    }
+
+    /// Worst alert on screen.
+    pub fn severity(&self) -> Option<Severity> {
+        self.alerts.iter().map(|alert| alert.severity).max()
+    }
+
+    /// Silences the alerts on screen until they clear or get worse.
+    pub fn acknowledge_alerts(&mut self) {
+        for state in self.alert_states.iter_mut().flatten() {
+            state.acknowledge();
+        }
+        self.alerts.clear();
+    }
+
+    pub fn status(&self, lines: Vec<String>) -> Frame {
+        Frame::status(fit_lines(lines, &self.device)).on_screen(self.index)
+    }
+
+    /// Renders the current page, or the alert frame while a sensor on any page of the
+    /// screen is past a threshold.
+    pub fn frame(&self, hwinfo: &Hwinfo) -> Result<Frame, anyhow::Error> {
+        if let Some(severity) = self.severity() {
+            let lines = fit_lines(alert_lines(&self.alerts, self.device.lines), &self.device);
+            return Ok(Frame::alert(severity, lines).on_screen(self.index));
+        }
+        let page_counter = self.page_counter;
+        let frame = if self.summary {
+            let lines = format_summary_value(
+                hwinfo,
+                self.vertical.unwrap_or(true),
+                self.gpu,
+                &self.summary_format,
+                self.device.width,
+            )?;
+            Frame::page(page_counter + 1, fit_lines(lines, &self.device))
+        } else {
+            // Custom Senors
+            let pages_sensors = self.pages[page_counter];
+            let mut labels = vec![""; CUSTOM_SENSORS];
+            let mut units = vec![""; CUSTOM_SENSORS];
+            let mut values = vec![String::new(); CUSTOM_SENSORS];
+            let mut readings = vec![None; CUSTOM_SENSORS];
+
+            run_sensors(
+                pages_sensors,
+                &mut labels,
+                &mut units,
+                &mut values,
+                &mut readings,
+                &self.filters[page_counter],
+                hwinfo,
+                self.decimal,
+            )?;
+            let image = match self.renders[page_counter] {
+                PageRender::Bitmap { .. } => Some(render_bitmap_page(
+                    pages_sensors,
+                    self.sensors_per_line,
+                    &labels,
+                    &values,
+                    &units,
+                    &readings,
+                    &self.sparklines[page_counter],
+                    hwinfo,
+                    &self.device,
+                )?),
+                PageRender::Text { .. } => None,
+            };
+            let bars = line_bar_values(pages_sensors, &self.bars[page_counter], &readings, hwinfo)?;
+            let mut lines = format_custom_value(
+                pages_sensors,
+                self.sensors_per_line,
+                self.device.lines,
+                self.device.width,
+                labels,
+                values,
+                units,
+            )?;
+            for (line, speed) in lines.iter_mut().zip(self.marquees[page_counter].iter()) {
+                if let Some(speed) = speed {
+                    *line = marquee(line, self.page_ticks * speed, self.device.width);
+                }
+            }
+            let lines = fit_lines(lines, &self.device);
+            match image {
+                Some(image) => Frame::page(page_counter + 1, lines).with_image(image),
+                None => Frame::page(page_counter + 1, lines).with_bars(bars),
+            }
+        };
+        Ok(frame.on_screen(self.index))
+    }
+}
@@ -1,6 +1,7 @@
-use crate::consts::STYLE;
 use console::Term;
 use dialoguer::Input;
+use hwinfo_steelseries_oled::consts::STYLE;
+use hwinfo_steelseries_oled::device::PROFILES;
 use hwinfo_steelseries_oled::Hwinfo;
 use ini::Ini;
 
@@ -50,17 +51,25 @@ pub fn settings_create_config(term: &Term, hwinfo: &Hwinfo) -> Result<Ini, anyho
             conf.with_section(Some("Main")).set("gpu", gpu_selected);
         }
     } else {
-        println!("\n3 lines will fit on the Arctis(or Nova) Pro screen, and 2 on the Apex Pro.");
-        let lines: u8 = match Input::new()
-            .with_prompt("How many lines? (2-3)")
+        println!("\nWhich screen?");
+        for (i, profile) in PROFILES.iter().enumerate() {
+            println!(
+                "{}) {} - {} ({} lines)",
+                i, profile.description, profile.name, profile.lines
+            );
+        }
+        let profile = match Input::<usize>::new()
+            .with_prompt(format!("0..{}", PROFILES.len() - 1))
             .interact_text()
         {
-            Ok(lines) => match lines {
-                2 | 3 => lines,
-                _ => 3,
+            Ok(profile) => match PROFILES.get(profile) {
+                Some(profile) => profile,
+                None => &PROFILES[0],
             },
-            Err(_) => 3,
+            Err(_) => &PROFILES[0],
         };
+        conf.with_section(Some("Main")).set("device", profile.name);
+        let lines = profile.lines as u8;
         let sensors_per_line: u8 = Input::new()
             .with_prompt("How many sensors per line? (1-3)")
             .interact_text()?;
@@ -0,0 +1,50 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+use winapi::um::wincon::{CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT};
+
+static RUNNING: AtomicBool = AtomicBool::new(true);
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+pub fn running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+pub fn request() {
+    RUNNING.store(false, Ordering::Relaxed);
+}
+
+/// Lets a pending console close continue once cleanup is done.
+pub fn finished() {
+    FINISHED.store(true, Ordering::Relaxed);
+}
+
+/// Turns Ctrl+C, Ctrl+Break and closing the console window into a shutdown request.
+pub fn install_console_handler() -> Result<(), anyhow::Error> {
+    if unsafe { SetConsoleCtrlHandler(Some(console_handler), TRUE) } == 0 {
+        return Err(anyhow::Error::new(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn console_handler(ctrl_type: DWORD) -> BOOL {
+    match ctrl_type {
+        CTRL_C_EVENT | CTRL_BREAK_EVENT => {
+            request();
+            TRUE
+        }
+        CTRL_CLOSE_EVENT => {
+            request();
+            // Windows kills the process as soon as this returns, so hold it until main
+            // has unregistered from SteelSeries GG (it allows about 5 seconds).
+            let deadline = Instant::now() + Duration::from_secs(4);
+            while !FINISHED.load(Ordering::Relaxed) && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            TRUE
+        }
+        _ => FALSE,
+    }
+}
@@ -0,0 +1,76 @@
+//! Recent samples of a reading for the sparkline widget on bitmap pages.
+
+use std::collections::VecDeque;
+
+/// Ring buffer of the last `capacity` samples, oldest first.
+#[derive(Clone, Debug)]
+pub struct SampleBuffer {
+    capacity: usize,
+    samples: VecDeque<f64>,
+}
+
+impl SampleBuffer {
+    pub fn new(capacity: usize) -> SampleBuffer {
+        SampleBuffer {
+            capacity: capacity.max(1),
+            samples: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    /// Appends a sample, dropping the oldest one once the buffer is full.
+    pub fn push(&mut self, value: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    pub fn samples(&self) -> impl DoubleEndedIterator<Item = &f64> + ExactSizeIterator {
+        self.samples.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Lowest and highest sample, ignoring NaN.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.samples
+            .iter()
+            .filter(|sample| !sample.is_nan())
+            .fold(None, |range, &sample| match range {
+                Some((min, max)) => Some((f64::min(min, sample), f64::max(max, sample))),
+                None => Some((sample, sample)),
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SparklineStyle {
+    Line,
+    Bars,
+}
+
+impl SparklineStyle {
+    pub fn from_name(name: &str) -> Option<SparklineStyle> {
+        match name.to_lowercase().as_str() {
+            "line" | "true" => Some(SparklineStyle::Line),
+            "bars" | "bar" => Some(SparklineStyle::Bars),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sparkline {
+    pub style: SparklineStyle,
+    pub samples: SampleBuffer,
+}
@@ -1,14 +1,14 @@
 use gamesense::handler::screen::{self, ScreenHandler};
+use serde_json::{json, Value};
 
-pub fn page_handler(
-    ttl: isize,
-    label_1: &str,
-    label_2: &str,
-    label_3: &str,
-    bold: Option<bool>,
-) -> ScreenHandler {
+use crate::device::DeviceProfile;
+use crate::lighting::{Rgb, Zone};
+
+/// Multi-line handler with any mix of text and progress bar lines for `device_type`
+/// (`screened` or a sized `screened-WxH`).
+pub fn lines_handler(device_type: &str, ttl: isize, lines: Vec<screen::LineData>) -> ScreenHandler {
     screen::ScreenHandler::new(
-        "screened",
+        device_type,
         "one",
         screen::ScreenDataDefinition::StaticScreenDataDefinition(
             screen::StaticScreenDataDefinition(vec![screen::ScreenFrameData::MultiLineFrameData(
@@ -18,55 +18,103 @@ pub fn page_handler(
                         icon_id: Some(screen::Icon::None),
                         repeats: Some(screen::Repeat::Bool(false)),
                     }),
-                    lines: vec![
-                        screen::LineData {
-                            type_options: screen::LineDataType::TextModifiersData(
-                                screen::TextModifiersData {
-                                    has_text: true,
-                                    prefix: None,
-                                    suffix: None,
-                                    bold: bold,
-                                    wrap: None,
-                                },
-                            ),
-                            data_accessor_data: Some(screen::DataAccessorData {
-                                arg: None,
-                                context_frame_key: Some(String::from(label_1)),
-                            }),
-                        },
-                        screen::LineData {
-                            type_options: screen::LineDataType::TextModifiersData(
-                                screen::TextModifiersData {
-                                    has_text: true,
-                                    prefix: None,
-                                    suffix: None,
-                                    bold: bold,
-                                    wrap: None,
-                                },
-                            ),
-                            data_accessor_data: Some(screen::DataAccessorData {
-                                arg: None,
-                                context_frame_key: Some(String::from(label_2)),
-                            }),
-                        },
-                        screen::LineData {
-                            type_options: screen::LineDataType::TextModifiersData(
-                                screen::TextModifiersData {
-                                    has_text: true,
-                                    prefix: None,
-                                    suffix: None,
-                                    bold: bold,
-                                    wrap: None,
-                                },
-                            ),
-                            data_accessor_data: Some(screen::DataAccessorData {
-                                arg: None,
-                                context_frame_key: Some(String::from(label_3)),
-                            }),
-                        },
-                    ],
+                    lines,
                 },
             )]),
         ),
     )
 }
+
+/// Line showing the text at `key` of the event frame.
+pub fn text_line(key: &str, bold: Option<bool>) -> screen::LineData {
+    screen::LineData {
+        type_options: screen::LineDataType::TextModifiersData(screen::TextModifiersData {
+            has_text: true,
+            prefix: None,
+            suffix: None,
+            bold,
+            wrap: None,
+        }),
+        data_accessor_data: Some(screen::DataAccessorData {
+            arg: None,
+            context_frame_key: Some(String::from(key)),
+        }),
+    }
+}
+
+/// GG's own progress bar, filled to the 0-100 number at `key` of the event frame.
+pub fn progress_bar_line(key: &str) -> screen::LineData {
+    screen::LineData {
+        type_options: screen::LineDataType::ProgressBarData(screen::ProgressBarData {
+            has_progress_bar: true,
+        }),
+        data_accessor_data: Some(screen::DataAccessorData {
+            arg: None,
+            context_frame_key: Some(String::from(key)),
+        }),
+    }
+}
+
+/// Handler for pages drawn into a framebuffer. The picture comes from the
+/// `image-data-WxH` key of each event frame, `image_data` is only the fallback.
+pub fn image_handler(ttl: isize, device: &DeviceProfile) -> ScreenHandler {
+    screen::ScreenHandler::new(
+        device.image_device_type().as_str(),
+        "one",
+        screen::ScreenDataDefinition::StaticScreenDataDefinition(
+            screen::StaticScreenDataDefinition(vec![screen::ScreenFrameData::ImageFrameData(
+                screen::ImageFrameData {
+                    has_text: false,
+                    frame_modifiers_data: Some(screen::FrameModifiersData {
+                        length_millis: Some(ttl * 1000),
+                        icon_id: None,
+                        repeats: None,
+                    }),
+                    image_data: vec![0; device.width * device.height / 8],
+                },
+            )]),
+        ),
+    )
+}
+
+/// Color handler lighting `zone` with the color of the range the event value falls in,
+/// flashing `frequency` times per second when set.
+pub fn color_handler(zone: &Zone, ranges: &[(isize, isize, Rgb)], frequency: Option<f64>) -> Value {
+    let mut handler = json!({
+        "device-type": zone.device_type,
+        "zone": zone.zone,
+        "mode": "color",
+        "color": ranges
+            .iter()
+            .map(|(low, high, color)| json!({"low": low, "high": high, "color": rgb(color)}))
+            .collect::<Vec<Value>>(),
+    });
+    if let Some(frequency) = frequency {
+        handler["rate"] = json!({"frequency": frequency});
+    }
+    handler
+}
+
+/// Tactile handler playing one of GG's predefined vibration patterns on every event.
+pub fn tactile_handler(zone: &Zone, pattern: &str) -> Value {
+    json!({
+        "device-type": zone.device_type,
+        "zone": zone.zone,
+        "mode": "vibrate",
+        "pattern": [{"type": pattern}],
+    })
+}
+
+/// Color handler blending from `zero` to `hundred` as the event value goes from 0 to 100.
+pub fn gradient_handler(zone: &Zone, zero: &Rgb, hundred: &Rgb) -> Value {
+    json!({
+        "device-type": zone.device_type,
+        "zone": zone.zone,
+        "mode": "color",
+        "color": {"gradient": {"zero": rgb(zero), "hundred": rgb(hundred)}},
+    })
+}
+
+fn rgb(color: &Rgb) -> Value {
+    json!({"red": color.red, "green": color.green, "blue": color.blue})
+}
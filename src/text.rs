@@ -0,0 +1,261 @@
+//! Width model of the proportional font GG uses for text lines on the OLED, and helpers
+//! to make fields fit a line.
+//!
+//! Widths are in pixels and include the gap after the glyph. They approximate the default
+//! (non bold) font on a 128 px wide screen, erring on the wide side.
+
+/// Horizontal alignment of a cell within its column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Align {
+    pub fn from_name(name: &str) -> Option<Align> {
+        match name.to_lowercase().as_str() {
+            "left" => Some(Align::Left),
+            "right" => Some(Align::Right),
+            "center" | "centre" => Some(Align::Center),
+            _ => None,
+        }
+    }
+}
+
+pub const SPACE_WIDTH: usize = 4;
+
+pub fn char_width(c: char) -> usize {
+    match c {
+        ' ' => SPACE_WIDTH,
+        'i' | 'l' | '!' | '|' | '.' | ',' | ':' | ';' | '\'' | '`' => 3,
+        'I' | '1' | 'j' | 't' | 'f' | 'r' | '(' | ')' | '[' | ']' | '°' => 5,
+        'm' | 'w' | 'M' | 'W' | '%' | '@' => 9,
+        c if c.is_ascii() => 7,
+        // Arrows, clocks and other symbols
+        '\u{2000}'..='\u{2BFF}' => 9,
+        // Emoji and everything else GG falls back to a wide glyph for
+        _ => 12,
+    }
+}
+
+pub fn text_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Longest prefix of `text` that fits in `max_width`.
+pub fn truncate(text: &str, max_width: usize) -> String {
+    let mut width = 0;
+    let mut truncated = String::new();
+    for c in text.chars() {
+        width += char_width(c);
+        if width > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated
+}
+
+/// Pads `text` with spaces up to `width`. Spaces are 4 px, so the result can be up to
+/// 3 px short of `width`.
+pub fn pad(text: &str, width: usize, align: Align) -> String {
+    let spaces = width.saturating_sub(text_width(text)) / SPACE_WIDTH;
+    let (before, after) = match align {
+        Align::Left => (0, spaces),
+        Align::Right => (spaces, 0),
+        Align::Center => (spaces / 2, spaces - spaces / 2),
+    };
+    format!("{}{}{}", " ".repeat(before), text, " ".repeat(after))
+}
+
+const SI_PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
+
+/// Shorter spellings of a field, from the least to the most lossy: the number in it
+/// without decimals, then scaled to the next SI prefix of its unit (`4123MHz` ->
+/// `4.1GHz`, `1234.6k/s` -> `1.2M/s`). Only plain numbers, rates and units that already
+/// carry a prefix are scaled, `1234RPM` stays as it is.
+pub fn abbreviations(text: &str) -> Vec<String> {
+    let start = match text.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => start,
+        None => return Vec::new(),
+    };
+    let end = text[start..]
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map_or(text.len(), |end| start + end);
+    let number = match text[start..end].parse::<f64>() {
+        Ok(number) => number,
+        Err(_) => return Vec::new(),
+    };
+    let (prefix, suffix) = (&text[..start], &text[end..]);
+    let mut shorter = Vec::new();
+    if text[start..end].contains('.') {
+        shorter.push(format!("{}{:.0}{}", prefix, number, suffix));
+    }
+    let unit = suffix.trim_start();
+    let space = &suffix[..suffix.len() - unit.len()];
+    let (mut step, unit) = match unit.chars().next() {
+        None | Some('/') => (0, unit),
+        Some(first) => {
+            // `K` is a common spelling of kilo, but `m` is milli and not mega
+            let si = if first == 'K' { 'k' } else { first };
+            match SI_PREFIXES.iter().position(|prefix| prefix.starts_with(si)) {
+                Some(step) => (step, &unit[first.len_utf8()..]),
+                None => return shorter,
+            }
+        }
+    };
+    let mut scaled = number;
+    while scaled >= 1000.0 && step + 1 < SI_PREFIXES.len() {
+        scaled /= 1000.0;
+        step += 1;
+    }
+    if scaled != number {
+        shorter.push(format!(
+            "{}{:.1}{}{}{}",
+            prefix, scaled, space, SI_PREFIXES[step], unit
+        ));
+    }
+    shorter
+}
+
+/// `text` if it fits in `max_width`, otherwise the first abbreviation that does, otherwise
+/// the truncated text.
+pub fn fit(text: &str, max_width: usize) -> String {
+    if text_width(text) <= max_width {
+        return text.to_string();
+    }
+    for shorter in abbreviations(text) {
+        if text_width(&shorter) <= max_width {
+            return shorter;
+        }
+    }
+    truncate(text, max_width)
+}
+
+/// Lays `cells` out in equal columns across `max_width`, so the same column lines up on
+/// every line. A cell wider than its column pushes the next ones right as long as the
+/// line still fits; otherwise the wide cells share what the narrow ones leave and get
+/// abbreviated or truncated to that.
+pub fn columns(cells: &[String], max_width: usize, align: Align) -> String {
+    if cells.is_empty() {
+        return String::new();
+    }
+    let column_width = max_width / cells.len();
+    let gaps = (cells.len() - 1) * SPACE_WIDTH;
+    let natural = cells.iter().map(|cell| text_width(cell)).sum::<usize>() + gaps;
+    let cells = if natural <= max_width {
+        cells.to_vec()
+    } else {
+        let narrow = cells
+            .iter()
+            .map(|cell| text_width(cell))
+            .filter(|width| *width <= column_width)
+            .collect::<Vec<usize>>();
+        let wide = cells.len() - narrow.len();
+        let share = max_width.saturating_sub(narrow.iter().sum::<usize>() + gaps) / wide.max(1);
+        cells
+            .iter()
+            .map(|cell| {
+                if text_width(cell) <= column_width {
+                    cell.to_string()
+                } else {
+                    fit(cell, share)
+                }
+            })
+            .collect()
+    };
+
+    let mut line = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        let width = text_width(cell);
+        let column_start = i * column_width;
+        let start = match align {
+            Align::Left => column_start,
+            Align::Right => (column_start + column_width).saturating_sub(width),
+            Align::Center => column_start + column_width.saturating_sub(width) / 2,
+        }
+        // Pull a wide last cell back instead of running off the screen
+        .min(max_width.saturating_sub(width));
+        let current = text_width(&line);
+        let mut spaces = start.saturating_sub(current) / SPACE_WIDTH;
+        if i > 0 && spaces == 0 {
+            spaces = 1;
+        }
+        line.push_str(&" ".repeat(spaces));
+        line.push_str(cell);
+    }
+    truncate(line.trim_end(), max_width)
+}
+
+/// One cell of a [`grid`] line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub text: String,
+    /// Width in pixels, `None` shares the space left by the fixed cells.
+    pub width: Option<usize>,
+    pub align: Align,
+}
+
+/// Lays out cells with their own width and alignment, `separator` between them. Cells
+/// are abbreviated or truncated to their width, so later cells never move.
+pub fn grid(cells: &[Cell], separator: &str, max_width: usize) -> String {
+    if cells.is_empty() {
+        return String::new();
+    }
+    let separator_width = text_width(separator);
+    let fixed = cells.iter().filter_map(|cell| cell.width).sum::<usize>()
+        + separator_width * (cells.len() - 1);
+    let flexible = cells.iter().filter(|cell| cell.width.is_none()).count();
+    let share = max_width.saturating_sub(fixed) / flexible.max(1);
+
+    let mut line = String::new();
+    let mut end = 0;
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            line.push_str(separator);
+            end += separator_width;
+        }
+        let width = cell.width.unwrap_or(share);
+        let text = fit(&cell.text, width);
+        end += width;
+        let free = end.saturating_sub(text_width(&line) + text_width(&text));
+        let before = match cell.align {
+            Align::Left => 0,
+            Align::Right => free,
+            Align::Center => free / 2,
+        };
+        line.push_str(&" ".repeat(before / SPACE_WIDTH));
+        line.push_str(&text);
+        if i < cells.len() - 1 {
+            let after = end.saturating_sub(text_width(&line));
+            line.push_str(&" ".repeat(after / SPACE_WIDTH));
+        }
+    }
+    truncate(line.trim_end(), max_width)
+}
+
+/// Space between the end of a scrolling text and its start coming round again.
+pub const MARQUEE_GAP: &str = "   ";
+
+/// Window of `text` scrolled `offset` characters to the left, wrapping around. Text that
+/// fits in `max_width` doesn't scroll.
+pub fn marquee(text: &str, offset: usize, max_width: usize) -> String {
+    if text_width(text) <= max_width {
+        return text.to_string();
+    }
+    let looped = text
+        .chars()
+        .chain(MARQUEE_GAP.chars())
+        .collect::<Vec<char>>();
+    let mut window = String::new();
+    let mut width = 0;
+    for c in looped.iter().cycle().skip(offset % looped.len()) {
+        width += char_width(*c);
+        if width > max_width {
+            break;
+        }
+        window.push(*c);
+    }
+    window
+}
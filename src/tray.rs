@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use tray_icon::menu::{Menu, MenuEvent, MenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+use crate::shutdown;
+
+pub enum TrayAction {
+    AcknowledgeAlerts,
+    Quit,
+}
+
+pub struct Tray {
+    _icon: TrayIcon,
+    acknowledge: MenuItem,
+    quit: MenuItem,
+}
+
+impl Tray {
+    pub fn new() -> Result<Tray, anyhow::Error> {
+        let icon = Icon::from_path("assets/hwinfo-steelseries-icon.ico", Some((64, 64)))?;
+        let menu = Menu::new();
+        let acknowledge = MenuItem::new("Acknowledge alerts", true, None);
+        let quit = MenuItem::new("Quit", true, None);
+        menu.append(&acknowledge)?;
+        menu.append(&quit)?;
+        let tray = TrayIconBuilder::new()
+            .with_tooltip("HWiNFO-SteelSeries")
+            .with_icon(icon)
+            .with_menu(Box::new(menu))
+            .build()?;
+        Ok(Tray {
+            _icon: tray,
+            acknowledge,
+            quit,
+        })
+    }
+
+    /// Sleeps for `duration` while keeping the tray menu responsive. Returns early once
+    /// a shutdown has been requested.
+    pub fn wait(&self, duration: Duration) -> Vec<TrayAction> {
+        let mut actions = Vec::new();
+        let end = Instant::now() + duration;
+        while Instant::now() < end && shutdown::running() {
+            pump_messages();
+            while let Ok(event) = MenuEvent::receiver().try_recv() {
+                if event.id == *self.acknowledge.id() {
+                    actions.push(TrayAction::AcknowledgeAlerts);
+                } else if event.id == *self.quit.id() {
+                    actions.push(TrayAction::Quit);
+                }
+            }
+            if !actions.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50).min(end - Instant::now()));
+        }
+        actions
+    }
+}
+
+// The tray icon's window lives on this thread, so its messages have to be dispatched here
+fn pump_messages() {
+    use std::ptr;
+    use winapi::um::winuser::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Units of HWiNFO readings and conversions between them, for `convert_N`.
+
+use anyhow::bail;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantity {
+    Temperature,
+    Data,
+    DataRate,
+    Frequency,
+    Power,
+    FanSpeed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Unit {
+    pub name: &'static str,
+    /// Shown after the value when `unit_N` isn't set.
+    pub suffix: &'static str,
+    pub quantity: Quantity,
+    /// `value * scale + offset` is the value in the base unit of the quantity.
+    scale: f64,
+    offset: f64,
+}
+
+const fn unit(name: &'static str, suffix: &'static str, quantity: Quantity, scale: f64) -> Unit {
+    Unit {
+        name,
+        suffix,
+        quantity,
+        scale,
+        offset: 0.0,
+    }
+}
+
+const KIB: f64 = 1024.0;
+
+pub const UNITS: [Unit; 24] = [
+    unit("C", "°C", Quantity::Temperature, 1.0),
+    Unit {
+        name: "F",
+        suffix: "°F",
+        quantity: Quantity::Temperature,
+        scale: 5.0 / 9.0,
+        offset: -32.0 * 5.0 / 9.0,
+    },
+    Unit {
+        name: "K",
+        suffix: "K",
+        quantity: Quantity::Temperature,
+        scale: 1.0,
+        offset: -273.15,
+    },
+    unit("B", "B", Quantity::Data, 1.0),
+    unit("KB", "KB", Quantity::Data, KIB),
+    unit("MB", "MB", Quantity::Data, KIB * KIB),
+    unit("GB", "GB", Quantity::Data, KIB * KIB * KIB),
+    unit("TB", "TB", Quantity::Data, KIB * KIB * KIB * KIB),
+    unit("B/s", "B/s", Quantity::DataRate, 1.0),
+    unit("KB/s", "KB/s", Quantity::DataRate, KIB),
+    unit("MB/s", "MB/s", Quantity::DataRate, KIB * KIB),
+    unit("GB/s", "GB/s", Quantity::DataRate, KIB * KIB * KIB),
+    unit("Kbit/s", "Kb/s", Quantity::DataRate, 1e3 / 8.0),
+    unit("Mbit/s", "Mb/s", Quantity::DataRate, 1e6 / 8.0),
+    unit("Gbit/s", "Gb/s", Quantity::DataRate, 1e9 / 8.0),
+    unit("Hz", "Hz", Quantity::Frequency, 1.0),
+    unit("kHz", "kHz", Quantity::Frequency, 1e3),
+    unit("MHz", "MHz", Quantity::Frequency, 1e6),
+    unit("GHz", "GHz", Quantity::Frequency, 1e9),
+    unit("mW", "mW", Quantity::Power, 1e-3),
+    unit("W", "W", Quantity::Power, 1.0),
+    unit("kW", "kW", Quantity::Power, 1e3),
+    unit("RPM", "RPM", Quantity::FanSpeed, 1.0),
+    // Share of the fan's top speed, `max_N`
+    unit("%", "%", Quantity::FanSpeed, f64::NAN),
+];
+
+impl Unit {
+    /// Exact name first, then ignoring case. A leading `°` is optional.
+    pub fn from_name(name: &str) -> Option<Unit> {
+        let name = name.trim();
+        let name = name.strip_prefix('°').unwrap_or(name);
+        UNITS
+            .iter()
+            .find(|unit| unit.name == name)
+            .or_else(|| {
+                UNITS
+                    .iter()
+                    .find(|unit| unit.name.eq_ignore_ascii_case(name))
+            })
+            .copied()
+    }
+
+    /// Percent of the full scale rather than a fixed size.
+    pub fn is_relative(&self) -> bool {
+        self.scale.is_nan()
+    }
+}
+
+/// `convert_N="FROM/TO"`, e.g. `MB/GB`, `C/F` or `KB/s/Mbit/s`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversion {
+    pub from: Unit,
+    pub to: Unit,
+}
+
+impl Conversion {
+    pub fn parse(text: &str) -> Result<Conversion, anyhow::Error> {
+        // Rates have a slash of their own, so try every split
+        for (i, _) in text.match_indices('/') {
+            let (from, to) = match (Unit::from_name(&text[..i]), Unit::from_name(&text[i + 1..])) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+            if from.quantity != to.quantity {
+                bail!("Can't convert {} to {}", from.name, to.name);
+            }
+            return Ok(Conversion { from, to });
+        }
+        bail!(
+            "Unknown conversion \"{}\", expected FROM/TO with units among {}",
+            text,
+            UNITS
+                .iter()
+                .map(|unit| unit.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )
+    }
+
+    /// Whether [`Conversion::apply`] needs the full scale, for `RPM/%`.
+    pub fn is_relative(&self) -> bool {
+        self.from.is_relative() != self.to.is_relative()
+    }
+
+    /// `value` in `to`. `full_scale` is what counts as 100% (e.g. the top RPM) for relative
+    /// conversions and ignored otherwise.
+    pub fn apply(&self, value: f64, full_scale: f64) -> f64 {
+        match (self.from.is_relative(), self.to.is_relative()) {
+            (false, true) => value / full_scale * 100.0,
+            (true, false) => value * full_scale / 100.0,
+            (true, true) => value,
+            (false, false) => {
+                let base = value * self.from.scale + self.from.offset;
+                (base - self.to.offset) / self.to.scale
+            }
+        }
+    }
+}
+
+/// Units [`autoscale`] steps through, smallest first, with the short suffix it shows.
+const LADDERS: [&[(&str, &str)]; 4] = [
+    &[
+        ("B", "B"),
+        ("KB", "K"),
+        ("MB", "M"),
+        ("GB", "G"),
+        ("TB", "T"),
+    ],
+    &[
+        ("B/s", "B/s"),
+        ("KB/s", "K/s"),
+        ("MB/s", "M/s"),
+        ("GB/s", "G/s"),
+    ],
+    &[("Hz", "Hz"), ("kHz", "kHz"), ("MHz", "MHz"), ("GHz", "GHz")],
+    &[("mW", "mW"), ("W", "W"), ("kW", "kW")],
+];
+
+/// `value`, given in `unit`, in the largest unit it is at least 1 of, with as many
+/// decimals as fit in `width` characters. Moves up a unit when even the whole number is too
+/// wide. `None` for units without prefixes to step through.
+pub fn autoscale(value: f64, unit: Unit, width: usize) -> Option<(String, &'static str)> {
+    let ladder = LADDERS
+        .iter()
+        .find(|ladder| ladder.iter().any(|(name, _)| *name == unit.name))?;
+    let scaled = ladder
+        .iter()
+        .filter_map(|(name, short)| {
+            let to = Unit::from_name(name)?;
+            Some((Conversion { from: unit, to }.apply(value, 100.0), *short))
+        })
+        .collect::<Vec<(f64, &str)>>();
+    let start = scaled
+        .iter()
+        .rposition(|(scaled, _)| scaled.abs() >= 1.0)
+        .unwrap_or(0);
+    for (scaled, short) in scaled[start..].iter() {
+        let whole = format!("{:.0}", scaled);
+        if whole.len() > width {
+            continue;
+        }
+        // A decimal point needs room for at least one digit after it
+        let decimals = width.saturating_sub(whole.len() + 1);
+        let number = format!("{:.*}", decimals, scaled);
+        // Rounding up can add a digit, 9.96 -> 10.0
+        let number = if number.len() > width { whole } else { number };
+        return Some((number, short));
+    }
+    let (scaled, short) = scaled[scaled.len() - 1];
+    Some((format!("{:.0}", scaled), short))
+}
@@ -1,25 +1,40 @@
 use chrono::Local;
-use serde_json::{json, Value};
+use ini::Ini;
 
-use crate::Hwinfo;
-
-use crate::consts::{CUSTOM_SENSORS, DISPLAY_LINES};
+use crate::alert::{Alert, AlertSettings, AlertState, Thresholds};
+use crate::bitmap::{BitmapIcon, Framebuffer};
+use crate::consts::{CUSTOM_SENSORS, TICK_RATE};
+use crate::counter::Counter;
+use crate::device::DeviceProfile;
+use crate::expr::{glob_match, Expr};
+use crate::filter::{Filter, FilterState};
+use crate::font::GLYPH_HEIGHT;
+use crate::format::NumberFormat;
+use crate::lighting::{AlertEffects, ColorGradient, Rgb, Zone};
+use crate::sparkline::{SampleBuffer, Sparkline, SparklineStyle};
+use crate::text::{columns, fit, grid, truncate, Align, Cell};
+use crate::units::{autoscale, Conversion, Quantity, Unit};
+use crate::{Hwinfo, HwinfoSensorsReadingElement};
 
+/// `filters` are the smoothed values of the sensors with `filter_k`, shown in place of the
+/// current reading.
+#[allow(clippy::too_many_arguments)]
 pub fn run_sensors<'a>(
     pages_sensors: &'a ini::Properties,
     labels: &mut Vec<&'a str>,
     units: &mut Vec<&'a str>,
     values: &mut Vec<String>,
+    readings: &mut Vec<Option<f64>>,
+    filters: &[Option<FilterState>],
     hwinfo: &Hwinfo,
     decimal: bool,
 ) -> Result<(), anyhow::Error> {
     for k in 0..CUSTOM_SENSORS {
-        let sensor = match pages_sensors.get(format!("sensor_{}", k)) {
+        let raw_sensor = match pages_sensors.get(format!("sensor_{}", k)) {
             Some(sensor) => sensor,
             None => continue,
-        }
-        .split(";")
-        .collect::<Vec<&str>>();
+        };
+        let sensor = raw_sensor.split(";").collect::<Vec<&str>>();
         let label = match pages_sensors.get(format!("label_{}", k)) {
             Some(label) => label,
             None => "",
@@ -38,99 +53,1115 @@ pub fn run_sensors<'a>(
             let now = Local::now();
             values[k] = now.format("%I:%M%P").to_string();
             continue;
+        } else if sensor[0] == "FILE" && sensor.len() == 2 {
+            // First line of a text file, e.g. the now playing track written by another app.
+            // A missing file just shows nothing.
+            labels[k] = label;
+            units[k] = unit;
+            values[k] = match std::fs::read_to_string(sensor[1]) {
+                Ok(contents) => contents.lines().next().unwrap_or("").trim().to_string(),
+                Err(_) => String::new(),
+            };
+            continue;
         }
-        let mut value = match hwinfo.get(sensor[0], sensor[1]) {
-            Some(value) => value,
-            None => {
-                return Err(anyhow::Error::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    format!("Sensor not found:\n\t{}\n\t{}", sensor[0], sensor[1]),
-                )))
-            }
-        }
-        .value;
-        match pages_sensors.get(format!("convert_{}", k)) {
-            Some(convert) => match convert {
-                "MB/GB" => value = value / 1024.0,
-                _ => {}
+        let (value, reading_unit) = match raw_sensor.strip_prefix("EXPR;") {
+            Some(expression) => expression_value(expression, hwinfo)
+                .map(|value| (value, String::new()))
+                .map_err(|err| {
+                    anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("sensor_{}: {}", k, err),
+                    ))
+                })?,
+            None => match (
+                hwinfo.get(sensor[0], sensor[1]),
+                sensor_counter(pages_sensors, k)?,
+            ) {
+                (Some(value), None) => (value.value, value.unit()),
+                (Some(value), Some(counter)) => match hwinfo.rate(sensor[0], sensor[1], &counter) {
+                    // A size counted up per second, e.g. MB -> MB/s
+                    Some(rate) => (rate, value.unit() + "/s"),
+                    // Blank until HWiNFO has polled twice, and for a poll after a reset
+                    None => {
+                        labels[k] = label;
+                        units[k] = unit;
+                        continue;
+                    }
+                },
+                (None, _) => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("Sensor not found:\n\t{}\n\t{}", sensor[0], sensor[1]),
+                    )))
+                }
             },
-            None => {}
         };
+        let value = convert_value(pages_sensors, k, value, hwinfo)?;
+        let value = match filters[k].as_ref().and_then(|filter| filter.value()) {
+            Some(filtered) => filtered,
+            None => value,
+        };
+        let mut unit = sensor_unit(pages_sensors, k);
         let value_string: String;
-        if decimal {
-            value_string = format!("{:.1}", &value);
+        if let Some(width) = autoscale_width(pages_sensors, k)? {
+            // Scaled from the converted unit, or the one HWiNFO reports
+            let from = match conversion(pages_sensors, k)? {
+                Some(conversion) => Some(conversion.to),
+                None => Unit::from_name(&reading_unit),
+            };
+            let (number, suffix) = match from.and_then(|from| autoscale(value, from, width)) {
+                Some(scaled) => scaled,
+                None => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "autoscale_{} needs a size, rate, frequency or power, set convert_{}",
+                            k, k
+                        ),
+                    )))
+                }
+            };
+            value_string = number;
+            unit = suffix;
         } else {
-            value_string = format!("{:02.0}", &value);
+            value_string = sensor_format(pages_sensors, k, decimal)?.format(value);
         }
         labels[k] = label;
         units[k] = unit;
         values[k] = value_string;
+        readings[k] = Some(value);
     }
     Ok(())
 }
 
+/// `autoscale_k`, the most characters the number of sensor `k` may take.
+fn autoscale_width(
+    pages_sensors: &ini::Properties,
+    k: usize,
+) -> Result<Option<usize>, anyhow::Error> {
+    match pages_sensors.get(format!("autoscale_{}", k)) {
+        Some(width) => Ok(Some(width.parse::<usize>()?)),
+        None => Ok(None),
+    }
+}
+
+/// `rate_k=true` shows the per-second change of a cumulative reading, rolling over at
+/// `rate_wrap_k` when set.
+pub fn sensor_counter(
+    pages_sensors: &ini::Properties,
+    k: usize,
+) -> Result<Option<Counter>, anyhow::Error> {
+    match pages_sensors.get(format!("rate_{}", k)) {
+        Some(rate) if rate.parse::<bool>()? => {}
+        _ => return Ok(None),
+    }
+    let wrap = match pages_sensors.get(format!("rate_wrap_{}", k)) {
+        Some(wrap) => Some(wrap.parse::<f64>()?),
+        None => None,
+    };
+    Ok(Some(Counter { wrap }))
+}
+
+/// `format_k`, otherwise `.1` with `decimal` and `02.0` without.
+pub fn sensor_format(
+    pages_sensors: &ini::Properties,
+    k: usize,
+    decimal: bool,
+) -> Result<NumberFormat, anyhow::Error> {
+    let key = format!("format_{}", k);
+    match pages_sensors.get(&key) {
+        Some(spec) => parse_format(&key, spec),
+        None if decimal => NumberFormat::parse(".1"),
+        None => NumberFormat::parse("02.0"),
+    }
+}
+
+fn parse_format(key: &str, spec: &str) -> Result<NumberFormat, anyhow::Error> {
+    NumberFormat::parse(spec).map_err(|err| {
+        anyhow::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{}: {}", key, err),
+        ))
+    })
+}
+
+/// `unit_k`, or the unit `convert_k` converts to.
+fn sensor_unit(pages_sensors: &ini::Properties, k: usize) -> &str {
+    match pages_sensors.get(format!("unit_{}", k)) {
+        Some(unit) => unit,
+        None => match conversion(pages_sensors, k) {
+            Ok(Some(conversion)) => conversion.to.suffix,
+            _ => "",
+        },
+    }
+}
+
+/// `convert_k`, e.g. `MB/GB` or `C/F`.
+pub fn conversion(
+    pages_sensors: &ini::Properties,
+    k: usize,
+) -> Result<Option<Conversion>, anyhow::Error> {
+    match pages_sensors.get(format!("convert_{}", k)) {
+        Some(convert) => match Conversion::parse(convert) {
+            Ok(conversion) => Ok(Some(conversion)),
+            Err(err) => Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("convert_{}: {}", k, err),
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// `value` of sensor `k` after `convert_k`. `RPM/%` takes 100% from `convert_max_k`, a
+/// number or a reading.
+fn convert_value(
+    pages_sensors: &ini::Properties,
+    k: usize,
+    value: f64,
+    hwinfo: &Hwinfo,
+) -> Result<f64, anyhow::Error> {
+    let conversion = match conversion(pages_sensors, k)? {
+        Some(conversion) => conversion,
+        None => return Ok(value),
+    };
+    let full_scale = match pages_sensors.get(format!("convert_max_{}", k)) {
+        Some(max) => parse_limit(max, hwinfo)?,
+        None if conversion.is_relative() => {
+            return Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("convert_{} needs convert_max_{}", k, k),
+            )))
+        }
+        None => 100.0,
+    };
+    Ok(conversion.apply(value, full_scale))
+}
+
+/// Sparklines of a page, from `graph_k=line|bars` and `graph_window_k` (seconds, default 60).
+pub fn page_sparklines(
+    pages_sensors: &ini::Properties,
+) -> Result<Vec<Option<Sparkline>>, anyhow::Error> {
+    let mut sparklines = vec![None; CUSTOM_SENSORS];
+    for (k, sparkline) in sparklines.iter_mut().enumerate() {
+        let style = match pages_sensors.get(format!("graph_{}", k)) {
+            Some("false") | None => continue,
+            Some(graph) => match SparklineStyle::from_name(graph) {
+                Some(style) => style,
+                None => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "graph_{} must be \"line\" or \"bars\", got \"{}\"",
+                            k, graph
+                        ),
+                    )))
+                }
+            },
+        };
+        let window = match pages_sensors.get(format!("graph_window_{}", k)) {
+            Some(window) => window.parse::<u64>()?,
+            None => 60,
+        };
+        *sparkline = Some(Sparkline {
+            style,
+            samples: SampleBuffer::new((window * 1000 / TICK_RATE) as usize),
+        });
+    }
+    Ok(sparklines)
+}
+
+/// Records the current value of every graphed sensor of a page. Runs every tick for all
+/// pages so a graph is already filled when its page comes around.
+pub fn sample_sparklines(
+    pages_sensors: &ini::Properties,
+    sparklines: &mut [Option<Sparkline>],
+    hwinfo: &Hwinfo,
+) {
+    for (k, sparkline) in sparklines.iter_mut().enumerate() {
+        let sparkline = match sparkline {
+            Some(sparkline) => sparkline,
+            None => continue,
+        };
+        // Gaps keep the time axis honest when a reading drops out
+        let value = reading_value(pages_sensors, k, hwinfo);
+        sparkline.samples.push(value.unwrap_or(f64::NAN));
+    }
+}
+
+/// Smoothing of every sensor of a page, from `filter_k`, see [`Filter`].
+pub fn page_filters(
+    pages_sensors: &ini::Properties,
+) -> Result<Vec<Option<FilterState>>, anyhow::Error> {
+    let mut filters = vec![None; CUSTOM_SENSORS];
+    for (k, filter) in filters.iter_mut().enumerate() {
+        let spec = match pages_sensors.get(format!("filter_{}", k)) {
+            Some(spec) => spec,
+            None => continue,
+        };
+        match Filter::parse(spec) {
+            Ok(parsed) => *filter = Some(FilterState::new(parsed)),
+            Err(err) => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("filter_{}: {}", k, err),
+                )))
+            }
+        }
+    }
+    Ok(filters)
+}
+
+/// Feeds the current value of every filtered sensor of a page. Runs every tick for all
+/// pages, like [`sample_sparklines`], so the filters are warmed up when a page comes around.
+pub fn sample_filters(
+    pages_sensors: &ini::Properties,
+    filters: &mut [Option<FilterState>],
+    hwinfo: &Hwinfo,
+) {
+    for (k, filter) in filters.iter_mut().enumerate() {
+        if let Some(filter) = filter {
+            filter.push(reading_value(pages_sensors, k, hwinfo).unwrap_or(f64::NAN));
+        }
+    }
+}
+
+/// Value of an `EXPR;...` sensor, see [`Expr`].
+fn expression_value(expression: &str, hwinfo: &Hwinfo) -> Result<f64, anyhow::Error> {
+    Expr::parse(expression)?.eval(&|sensor, reading| matching_readings(hwinfo, sensor, reading))
+}
+
+/// Values of the readings matching `sensor` and `reading`, which can contain `*`.
+fn matching_readings(hwinfo: &Hwinfo, sensor: &str, reading: &str) -> Vec<f64> {
+    if !sensor.contains('*') && !reading.contains('*') {
+        return hwinfo
+            .get(sensor, reading)
+            .map(|element| element.value)
+            .into_iter()
+            .collect();
+    }
+    let mut values = Vec::new();
+    for (sensor_name, sensor_readings) in hwinfo.master_readings.sensors.iter() {
+        if !glob_match(sensor, sensor_name) {
+            continue;
+        }
+        for (reading_name, element) in sensor_readings.reading.iter() {
+            if glob_match(reading, reading_name) {
+                values.push(element.value);
+            }
+        }
+    }
+    values
+}
+
+/// Converted value of sensor `k` when it's an expression or a `Sensor;Reading` that HWiNFO
+/// currently has.
+fn reading_value(pages_sensors: &ini::Properties, k: usize, hwinfo: &Hwinfo) -> Option<f64> {
+    let raw_sensor = pages_sensors.get(format!("sensor_{}", k))?;
+    let value = match raw_sensor.strip_prefix("EXPR;") {
+        Some(expression) => expression_value(expression, hwinfo).ok()?,
+        None => match raw_sensor.split(";").collect::<Vec<&str>>()[..] {
+            [sensor, reading] => match sensor_counter(pages_sensors, k).ok()? {
+                Some(counter) => hwinfo.rate(sensor, reading, &counter)?,
+                None => hwinfo.get(sensor, reading)?.value,
+            },
+            _ => return None,
+        },
+    };
+    convert_value(pages_sensors, k, value, hwinfo).ok()
+}
+
+/// Alert thresholds of every sensor of a page, from `warn_k` and `crit_k`.
+pub fn page_thresholds(pages_sensors: &ini::Properties) -> Result<Vec<Thresholds>, anyhow::Error> {
+    let mut thresholds = vec![Thresholds::default(); CUSTOM_SENSORS];
+    for (k, threshold) in thresholds.iter_mut().enumerate() {
+        if let Some(warn) = pages_sensors.get(format!("warn_{}", k)) {
+            threshold.warn = Some(warn.parse::<f64>()?);
+        }
+        if let Some(crit) = pages_sensors.get(format!("crit_{}", k)) {
+            threshold.crit = Some(crit.parse::<f64>()?);
+        }
+    }
+    Ok(thresholds)
+}
+
+/// Updates the alert of every sensor of a page with its current reading and returns the
+/// ones to show.
+pub fn page_alerts(
+    pages_sensors: &ini::Properties,
+    thresholds: &[Thresholds],
+    states: &mut [AlertState],
+    settings: &AlertSettings,
+    hwinfo: &Hwinfo,
+    decimal: bool,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for (k, (threshold, state)) in thresholds.iter().zip(states.iter_mut()).enumerate() {
+        if threshold.is_empty() {
+            continue;
+        }
+        let value = reading_value(pages_sensors, k, hwinfo).unwrap_or(f64::NAN);
+        state.update(threshold, value, settings);
+        let severity = match state.shown() {
+            Some(severity) => severity,
+            None => continue,
+        };
+        // Checked when the screen was set up
+        let value_string = sensor_format(pages_sensors, k, decimal)
+            .unwrap_or_default()
+            .format(value);
+        alerts.push(Alert {
+            severity,
+            text: cell_text(
+                pages_sensors.get(format!("label_{}", k)).unwrap_or(""),
+                &value_string,
+                sensor_unit(pages_sensors, k),
+                CellPart::All,
+            ),
+        });
+    }
+    alerts
+}
+
+/// `[Alerts]` section: `hysteresis` in the unit of the readings, `delay` and `cooldown`
+/// in seconds. All default to 0.
+pub fn alert_settings(config_file: &Ini) -> Result<AlertSettings, anyhow::Error> {
+    let section = match config_file.section(Some("Alerts")) {
+        Some(section) => section,
+        None => return Ok(AlertSettings::default()),
+    };
+    let ticks = |seconds: u64| (seconds * 1000 / TICK_RATE) as usize;
+    Ok(AlertSettings {
+        hysteresis: match section.get("hysteresis") {
+            Some(hysteresis) => hysteresis.parse::<f64>()?,
+            None => 0.0,
+        },
+        delay: match section.get("delay") {
+            Some(delay) => ticks(delay.parse::<u64>()?),
+            None => 0,
+        },
+        cooldown: match section.get("cooldown") {
+            Some(cooldown) => ticks(cooldown.parse::<u64>()?),
+            None => 0,
+        },
+    })
+}
+
+/// Scale of the graph for sensor `k`: `min_k`/`max_k` when set, otherwise the range of the
+/// samples on screen.
+fn graph_range(
+    pages_sensors: &ini::Properties,
+    k: usize,
+    samples: &SampleBuffer,
+    hwinfo: &Hwinfo,
+) -> Result<(f64, f64), anyhow::Error> {
+    let (low, high) = samples.range().unwrap_or((0.0, 0.0));
+    let min = match pages_sensors.get(format!("min_{}", k)) {
+        Some(min) => parse_limit(min, hwinfo)?,
+        None => low,
+    };
+    let max = match pages_sensors.get(format!("max_{}", k)) {
+        Some(max) => parse_limit(max, hwinfo)?,
+        None => high,
+    };
+    Ok((min, max))
+}
+
+/// Range of the bar for sensor `k` when `bar_k=true`. `min_k`/`max_k` default to 0 and 100
+/// and can also name another reading, e.g. `max_3="GPU [#0]: ...;GPU Memory Total"`.
+pub fn bar_range(
+    pages_sensors: &ini::Properties,
+    k: usize,
+    hwinfo: &Hwinfo,
+) -> Result<Option<(f64, f64)>, anyhow::Error> {
+    let bar = match pages_sensors.get(format!("bar_{}", k)) {
+        Some(bar) => bar.parse::<bool>()?,
+        None => false,
+    };
+    if !bar {
+        return Ok(None);
+    }
+    Ok(Some(limits(pages_sensors, k, hwinfo)?))
+}
+
+/// `min_k`/`max_k`, defaulting to 0 and 100.
+fn limits(
+    pages_sensors: &ini::Properties,
+    k: usize,
+    hwinfo: &Hwinfo,
+) -> Result<(f64, f64), anyhow::Error> {
+    let min = match pages_sensors.get(format!("min_{}", k)) {
+        Some(min) => parse_limit(min, hwinfo)?,
+        None => 0.0,
+    };
+    let max = match pages_sensors.get(format!("max_{}", k)) {
+        Some(max) => parse_limit(max, hwinfo)?,
+        None => 100.0,
+    };
+    Ok((min, max))
+}
+
+/// Sensor shown as a native progress bar on each display line, from `lineN_bar=k`.
+pub fn line_bars(
+    pages_sensors: &ini::Properties,
+    display_lines: usize,
+) -> Result<Vec<Option<usize>>, anyhow::Error> {
+    let mut bars = vec![None; display_lines];
+    for (line, bar) in bars.iter_mut().enumerate() {
+        if let Some(k) = pages_sensors.get(format!("line{}_bar", line + 1)) {
+            let k = k.parse::<usize>()?;
+            if k >= CUSTOM_SENSORS {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "line{}_bar must be a sensor from 0 to {}",
+                        line + 1,
+                        CUSTOM_SENSORS - 1
+                    ),
+                )));
+            }
+            *bar = Some(k);
+        }
+    }
+    Ok(bars)
+}
+
+/// Fill of each progress bar line for the current readings.
+pub fn line_bar_values(
+    pages_sensors: &ini::Properties,
+    bars: &[Option<usize>],
+    readings: &[Option<f64>],
+    hwinfo: &Hwinfo,
+) -> Result<Vec<Option<f64>>, anyhow::Error> {
+    let mut values = Vec::new();
+    for bar in bars.iter() {
+        values.push(match bar {
+            Some(k) => Some(bar_fraction(
+                readings[*k].unwrap_or(0.0),
+                limits(pages_sensors, *k, hwinfo)?,
+            )),
+            None => None,
+        });
+    }
+    Ok(values)
+}
+
+fn parse_limit(limit: &str, hwinfo: &Hwinfo) -> Result<f64, anyhow::Error> {
+    if let Ok(number) = limit.parse::<f64>() {
+        return Ok(number);
+    }
+    let sensor = limit.split(";").collect::<Vec<&str>>();
+    if sensor.len() == 2 {
+        if let Some(reading) = hwinfo.get(sensor[0], sensor[1]) {
+            return Ok(reading.value);
+        }
+    }
+    Err(anyhow::Error::new(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Bar limit is neither a number nor a sensor: {}", limit),
+    )))
+}
+
+/// Fraction of `value` within `min..max`.
+pub fn bar_fraction(value: f64, (min, max): (f64, f64)) -> f64 {
+    if max <= min {
+        return 0.0;
+    }
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
+
 pub fn format_custom_value(
+    pages_sensors: &ini::Properties,
     sensors_per_line: u8,
+    display_lines: usize,
+    width: usize,
     labels: Vec<&str>,
     values: Vec<String>,
     units: Vec<&str>,
-) -> Value {
-    let mut value = json!({});
-    if sensors_per_line == 1 {
-        for i in 0..DISPLAY_LINES {
-            value[format!("line{}", i + 1)] =
-                json!(format!("{} {}{}", labels[i], values[i], units[i]));
-        }
-    } else if sensors_per_line == 2 {
-        value["line1"] = json!(format!(
-            "{} {}{} {} {}{}",
-            labels[0], values[0], units[0], labels[1], values[1], units[1]
-        ));
-        value["line2"] = json!(format!(
-            "{} {}{} {} {}{}",
-            labels[2], values[2], units[2], labels[3], values[3], units[3]
-        ));
-        value["line3"] = json!(format!(
-            "{} {}{} {} {}{}",
-            labels[4], values[4], units[4], labels[5], values[5], units[5]
-        ));
-    } else if sensors_per_line == 3 {
-        value["line1"] = json!(format!(
-            "{} {}{} {}{}{} {}{}{}",
-            labels[0],
-            values[0],
-            units[0],
-            labels[1],
-            values[1],
-            units[1],
-            labels[2],
-            values[2],
-            units[2]
-        ));
-        value["line2"] = json!(format!(
-            "{} {}{} {}{}{} {}{}{}",
-            labels[3],
-            values[3],
-            units[3],
-            labels[4],
-            values[4],
-            units[4],
-            labels[5],
-            values[5],
-            units[5]
-        ));
-        value["line3"] = json!(format!(
-            "{} {}{} {}{}{} {}{}{}",
-            labels[6],
-            values[6],
-            units[6],
-            labels[7],
-            values[7],
-            units[7],
-            labels[8],
-            values[8],
-            units[8]
+) -> Result<Vec<String>, anyhow::Error> {
+    let mut lines = Vec::new();
+    let sensors_per_line = (sensors_per_line as usize).max(1);
+    for i in 0..display_lines {
+        if line_marquee(pages_sensors, i + 1)?.is_some() {
+            // Kept whole, the screen scrolls through it
+            let cells = match line_layout(pages_sensors, i + 1)? {
+                Some(layout) => layout
+                    .cells
+                    .iter()
+                    .map(|(k, part)| cell_text(labels[*k], &values[*k], units[*k], *part))
+                    .collect::<Vec<String>>(),
+                None => (i * sensors_per_line..(i + 1) * sensors_per_line)
+                    .filter(|k| *k < CUSTOM_SENSORS)
+                    .map(|k| cell_text(labels[k], &values[k], units[k], CellPart::All))
+                    .collect::<Vec<String>>(),
+            };
+            lines.push(cells.join(" "));
+            continue;
+        }
+        if let Some(layout) = line_layout(pages_sensors, i + 1)? {
+            let cells = layout
+                .cells
+                .iter()
+                .zip(layout.widths.iter())
+                .enumerate()
+                .map(|(c, ((k, part), width))| Cell {
+                    text: cell_text(labels[*k], &values[*k], units[*k], *part),
+                    width: *width,
+                    align: *layout.aligns.get(c).unwrap_or(&Align::Left),
+                })
+                .collect::<Vec<Cell>>();
+            lines.push(grid(&cells, &layout.separator, width));
+            continue;
+        }
+        let cells = (i * sensors_per_line..(i + 1) * sensors_per_line)
+            .filter(|k| *k < CUSTOM_SENSORS)
+            .map(|k| cell_text(labels[k], &values[k], units[k], CellPart::All))
+            .collect::<Vec<String>>();
+        lines.push(match cells.len() {
+            1 => fit(&cells[0], width),
+            _ => columns(&cells, width, Align::Left),
+        });
+    }
+    Ok(lines)
+}
+
+/// Characters display line `line` (1-based) scrolls per tick when `lineN_marquee=true`.
+/// `lineN_marquee_speed` defaults to 2.
+pub fn line_marquee(
+    pages_sensors: &ini::Properties,
+    line: usize,
+) -> Result<Option<usize>, anyhow::Error> {
+    let marquee = match pages_sensors.get(format!("line{}_marquee", line)) {
+        Some(marquee) => marquee.parse::<bool>()?,
+        None => false,
+    };
+    if !marquee {
+        return Ok(None);
+    }
+    match pages_sensors.get(format!("line{}_marquee_speed", line)) {
+        Some(speed) => Ok(Some(speed.parse::<usize>()?)),
+        None => Ok(Some(2)),
+    }
+}
+
+/// What part of a sensor a grid cell shows: `3`, `3:label` or `3:value`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CellPart {
+    All,
+    Label,
+    Value,
+}
+
+fn cell_text(label: &str, value: &str, unit: &str, part: CellPart) -> String {
+    match part {
+        CellPart::Label => label.to_string(),
+        CellPart::Value => format!("{}{}", value, unit),
+        CellPart::All if label.is_empty() => format!("{}{}", value, unit),
+        CellPart::All => format!("{} {}{}", label, value, unit),
+    }
+}
+
+pub struct LineLayout {
+    pub cells: Vec<(usize, CellPart)>,
+    pub widths: Vec<Option<usize>>,
+    pub aligns: Vec<Align>,
+    pub separator: String,
+}
+
+/// Grid layout of display line `line` (1-based) from `lineN_cells`, `lineN_widths`,
+/// `lineN_align` and `lineN_separator`. `None` when the line has no `lineN_cells`.
+pub fn line_layout(
+    pages_sensors: &ini::Properties,
+    line: usize,
+) -> Result<Option<LineLayout>, anyhow::Error> {
+    let invalid = |key: &str, value: &str| {
+        anyhow::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid line{}_{}: {}", line, key, value),
+        ))
+    };
+    let cells = match pages_sensors.get(format!("line{}_cells", line)) {
+        Some(cells) => cells,
+        None => return Ok(None),
+    };
+    let mut parsed_cells = Vec::new();
+    for cell in cells.split(",").map(|cell| cell.trim()) {
+        let (k, part) = match cell.split_once(":") {
+            Some((k, "label")) => (k, CellPart::Label),
+            Some((k, "value")) => (k, CellPart::Value),
+            Some(_) => return Err(invalid("cells", cells)),
+            None => (cell, CellPart::All),
+        };
+        match k.trim().parse::<usize>() {
+            Ok(k) if k < CUSTOM_SENSORS => parsed_cells.push((k, part)),
+            _ => return Err(invalid("cells", cells)),
+        }
+    }
+
+    let mut widths = vec![None; parsed_cells.len()];
+    if let Some(value) = pages_sensors.get(format!("line{}_widths", line)) {
+        for (width, item) in widths.iter_mut().zip(value.split(",")) {
+            *width = match item.trim() {
+                "" | "*" => None,
+                item => Some(
+                    item.parse::<usize>()
+                        .map_err(|_| invalid("widths", value))?,
+                ),
+            };
+        }
+    }
+
+    let aligns = match pages_sensors.get(format!("line{}_align", line)) {
+        Some(value) => {
+            let mut aligns = Vec::new();
+            for item in value.split(",") {
+                aligns.push(Align::from_name(item.trim()).ok_or_else(|| invalid("align", value))?);
+            }
+            // A single alignment applies to every cell
+            if aligns.len() == 1 {
+                aligns = vec![aligns[0]; parsed_cells.len()];
+            }
+            aligns
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Some(LineLayout {
+        cells: parsed_cells,
+        widths,
+        aligns,
+        separator: pages_sensors
+            .get(format!("line{}_separator", line))
+            .unwrap_or(" ")
+            .to_string(),
+    }))
+}
+
+/// Color and tactile feedback of alerts from `[Alerts]`, `None` when neither a
+/// `warn_color`/`crit_color` nor a `warn_tactile`/`crit_tactile` is set.
+pub fn alert_effects(config_file: &Ini) -> Result<Option<AlertEffects>, anyhow::Error> {
+    let section = match config_file.section(Some("Alerts")) {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+    let color = |key: &str| -> Result<Option<Rgb>, anyhow::Error> {
+        match section.get(key) {
+            Some(color) => match Rgb::parse(color) {
+                Some(color) => Ok(Some(color)),
+                None => Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} must be \"r,g,b\" or \"#rrggbb\", got \"{}\"",
+                        key, color
+                    ),
+                ))),
+            },
+            None => Ok(None),
+        }
+    };
+    let zone = |device_key: &str, zone_key: &str, device: &str, zone: &str| Zone {
+        device_type: section.get(device_key).unwrap_or(device).to_string(),
+        zone: section.get(zone_key).unwrap_or(zone).to_string(),
+    };
+    let effects = AlertEffects {
+        color_zone: zone("color_device", "color_zone", "rgb-per-key-zones", "all"),
+        warn_color: color("warn_color")?,
+        crit_color: color("crit_color")?,
+        flash: match section.get("flash") {
+            Some(flash) => flash.parse::<f64>()?,
+            None => 0.0,
+        },
+        tactile_zone: zone("tactile_device", "tactile_zone", "tactile", "one"),
+        warn_tactile: section.get("warn_tactile").map(String::from),
+        crit_tactile: section.get("crit_tactile").map(String::from),
+    };
+    if !effects.has_color() && effects.warn_tactile.is_none() && effects.crit_tactile.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(effects))
+}
+
+/// Gradients of `[Lighting]`: `sensor_k` colors `device_k`/`zone_k` from `min_color_k` at
+/// `min_k` to `max_color_k` at `max_k` (green to red by default).
+pub fn lighting_gradients(
+    lighting: &ini::Properties,
+) -> Result<Vec<(usize, ColorGradient)>, anyhow::Error> {
+    let mut gradients = Vec::new();
+    for k in 0..CUSTOM_SENSORS {
+        if lighting.get(format!("sensor_{}", k)).is_none() {
+            continue;
+        }
+        let color = |key: String, default: Rgb| -> Result<Rgb, anyhow::Error> {
+            match lighting.get(&key) {
+                Some(color) => Rgb::parse(color).ok_or_else(|| {
+                    anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} must be \"r,g,b\" or \"#rrggbb\", got \"{}\"",
+                            key, color
+                        ),
+                    ))
+                }),
+                None => Ok(default),
+            }
+        };
+        gradients.push((
+            k,
+            ColorGradient {
+                zone: Zone {
+                    device_type: lighting
+                        .get(format!("device_{}", k))
+                        .unwrap_or("rgb-per-key-zones")
+                        .to_string(),
+                    zone: lighting
+                        .get(format!("zone_{}", k))
+                        .unwrap_or("all")
+                        .to_string(),
+                },
+                zero: color(
+                    format!("min_color_{}", k),
+                    Rgb {
+                        red: 0,
+                        green: 255,
+                        blue: 0,
+                    },
+                )?,
+                hundred: color(
+                    format!("max_color_{}", k),
+                    Rgb {
+                        red: 255,
+                        green: 0,
+                        blue: 0,
+                    },
+                )?,
+            },
         ));
     }
-    value
+    Ok(gradients)
+}
+
+/// Where the reading behind gradient `k` is within `min_k..max_k`, `None` while HWiNFO
+/// doesn't have it.
+pub fn lighting_fraction(
+    lighting: &ini::Properties,
+    k: usize,
+    hwinfo: &Hwinfo,
+) -> Result<Option<f64>, anyhow::Error> {
+    match reading_value(lighting, k, hwinfo) {
+        Some(value) => Ok(Some(bar_fraction(value, limits(lighting, k, hwinfo)?))),
+        None => Ok(None),
+    }
+}
+
+/// Drops the lines and cuts the text that don't fit on `device`.
+pub fn fit_lines(lines: Vec<String>, device: &DeviceProfile) -> Vec<String> {
+    lines
+        .into_iter()
+        .take(device.lines)
+        .map(|line| truncate(&line, device.width))
+        .collect()
+}
+
+/// Draws a custom page into a framebuffer: one row per display line, `sensors_per_line`
+/// equal cells per row, each with an optional `icon_k`, the label, the value, a sparkline
+/// and a bar.
+#[allow(clippy::too_many_arguments)]
+pub fn render_bitmap_page(
+    pages_sensors: &ini::Properties,
+    sensors_per_line: u8,
+    labels: &[&str],
+    values: &[String],
+    units: &[&str],
+    readings: &[Option<f64>],
+    sparklines: &[Option<Sparkline>],
+    hwinfo: &Hwinfo,
+    device: &DeviceProfile,
+) -> Result<Framebuffer, anyhow::Error> {
+    let mut framebuffer = Framebuffer::new(device.width, device.height);
+    let sensors_per_line = sensors_per_line.max(1) as usize;
+    let row_height = device.height / device.lines;
+    let cell_width = device.width / sensors_per_line;
+    for line in 0..device.lines {
+        for cell in 0..sensors_per_line {
+            let k = line * sensors_per_line + cell;
+            if k >= CUSTOM_SENSORS {
+                continue;
+            }
+            let x = cell * cell_width;
+            let y = line * row_height;
+            let mut cursor = x;
+            let icon = pages_sensors
+                .get(format!("icon_{}", k))
+                .and_then(BitmapIcon::from_name);
+            if let Some(icon) = icon {
+                cursor = framebuffer.draw_icon(cursor, y, icon);
+            } else if !labels[k].is_empty() {
+                cursor = framebuffer.draw_text(cursor, y, labels[k]) + 2;
+            }
+            cursor = framebuffer.draw_text(cursor, y, &format!("{}{}", values[k], units[k]));
+            let mut cell_end = x + cell_width - 1;
+
+            if let Some(sparkline) = &sparklines[k] {
+                // Fills the rest of the cell next to the value
+                if cell_end > cursor + 4 {
+                    let range = graph_range(pages_sensors, k, &sparkline.samples, hwinfo)?;
+                    framebuffer.draw_sparkline(
+                        cursor + 1,
+                        y,
+                        cell_end - cursor - 1,
+                        row_height.saturating_sub(1),
+                        &sparkline.samples,
+                        range,
+                        sparkline.style,
+                    );
+                    cell_end = cursor;
+                }
+            }
+
+            let range = match (bar_range(pages_sensors, k, hwinfo)?, readings[k]) {
+                (Some(range), Some(reading)) => bar_fraction(reading, range),
+                _ => continue,
+            };
+            if row_height >= GLYPH_HEIGHT + 5 {
+                // Room for the bar under the text
+                let bar_height = (row_height - GLYPH_HEIGHT - 2).min(5);
+                framebuffer.draw_bar(x, y + GLYPH_HEIGHT + 1, cell_end - x, bar_height, range);
+            } else if cell_end > cursor + 4 {
+                framebuffer.draw_bar(cursor, y, cell_end - cursor, GLYPH_HEIGHT, range);
+            }
+        }
+    }
+    Ok(framebuffer)
+}
+
+/// How the summary styles show their fields.
+pub struct SummaryFormat {
+    /// Converts the HWiNFO °C readings, e.g. to Fahrenheit.
+    pub temperature: Conversion,
+    /// `autoscale`, width of the memory fields.
+    pub memory_width: Option<usize>,
+    pub cpu_temp: NumberFormat,
+    pub cpu_usage: NumberFormat,
+    pub gpu_temp: NumberFormat,
+    pub gpu_usage: NumberFormat,
+    pub mem_used: NumberFormat,
+    pub mem_free: NumberFormat,
+    pub mem_load: NumberFormat,
+}
+
+impl SummaryFormat {
+    /// `temperature`, `autoscale` and `format_cpu_temp`, `format_mem_used`... of `section`,
+    /// `temperature` falls back to `[Main]`. Fields without a format follow `decimal`.
+    pub fn from_section(
+        section: &ini::Properties,
+        config_main: &ini::Properties,
+        decimal: bool,
+    ) -> Result<SummaryFormat, anyhow::Error> {
+        // Celsius unless `temperature=F` (or K)
+        let celsius = Unit::from_name("C").unwrap();
+        let temperature = match section
+            .get("temperature")
+            .or(config_main.get("temperature"))
+        {
+            Some(name) => match Unit::from_name(name) {
+                Some(unit) if unit.quantity == Quantity::Temperature => Conversion {
+                    from: celsius,
+                    to: unit,
+                },
+                _ => {
+                    return Err(anyhow::Error::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("temperature must be C, F or K, got {}", name),
+                    )))
+                }
+            },
+            None => Conversion {
+                from: celsius,
+                to: celsius,
+            },
+        };
+        let memory_width = match section.get("autoscale") {
+            Some(width) => Some(width.parse::<usize>()?),
+            None => None,
+        };
+        let field = |name: &str| -> Result<NumberFormat, anyhow::Error> {
+            let key = format!("format_{}", name);
+            match section.get(&key) {
+                Some(spec) => parse_format(&key, spec),
+                None if decimal => NumberFormat::parse(".1"),
+                None => NumberFormat::parse(".0"),
+            }
+        };
+        Ok(SummaryFormat {
+            temperature,
+            memory_width,
+            cpu_temp: field("cpu_temp")?,
+            cpu_usage: field("cpu_usage")?,
+            gpu_temp: field("gpu_temp")?,
+            gpu_usage: field("gpu_usage")?,
+            mem_used: field("mem_used")?,
+            mem_free: field("mem_free")?,
+            mem_load: field("mem_load")?,
+        })
+    }
+}
+
+pub fn format_summary_value(
+    hwinfo: &Hwinfo,
+    vertical: bool,
+    gpu: &str,
+    format: &SummaryFormat,
+    width: usize,
+) -> Result<Vec<String>, anyhow::Error> {
+    let sensor_cpu_usage = hwinfo.find_first("Total CPU Usage")?;
+    let sensor_cpu_temp = hwinfo.find_first("CPU (Tctl/Tdie)")?;
+
+    let sensor_gpu_usage = hwinfo.find_first("GPU Core Load")?;
+    let sensor_gpu_temp: &HwinfoSensorsReadingElement;
+    if gpu == "" {
+        sensor_gpu_temp = hwinfo.find_first("GPU Temperature")?;
+    } else {
+        sensor_gpu_temp = match hwinfo.get(gpu, "GPU Temperature") {
+            Some(sensor) => sensor,
+            None => {
+                return Err(anyhow::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "GPU Temperature not found",
+                )))
+            }
+        };
+    }
+
+    let sensor_mem_used = hwinfo.find_first("Physical Memory Used")?;
+    let sensor_mem_free = hwinfo.find_first("Physical Memory Available")?;
+    let sensor_mem_load = hwinfo.find_first("Physical Memory Load")?;
+    let cpu_temp_cur_value = format.temperature.apply(sensor_cpu_temp.value, 100.0);
+    let cpu_usage_cur_value = sensor_cpu_usage.value;
+    let temp_unit = "°";
+    let usage_unit = "%";
+    let gpu_temp_cur_value = format.temperature.apply(sensor_gpu_temp.value, 100.0);
+    let gpu_usage_cur_value = sensor_gpu_usage.value;
+    let mem_unit = "G";
+    let mem_used = sensor_mem_used.value / 1024.0;
+    let mem_free = sensor_mem_free.value / 1024.0;
+    let mem_load = sensor_mem_load.value;
+    let memory = |gigabytes: f64, number: &NumberFormat| {
+        let gb = Unit::from_name("GB").unwrap();
+        match format
+            .memory_width
+            .and_then(|width| autoscale(gigabytes, gb, width))
+        {
+            Some((number, suffix)) => number + suffix,
+            None => number.format(gigabytes) + mem_unit,
+        }
+    };
+
+    let lines = if vertical {
+        vec![
+            columns(
+                &[
+                    String::from("CPU"),
+                    String::from("GPU"),
+                    String::from("MEM"),
+                ],
+                width,
+                Align::Left,
+            ),
+            columns(
+                &[
+                    format.cpu_temp.format(cpu_temp_cur_value) + temp_unit,
+                    format.gpu_temp.format(gpu_temp_cur_value) + temp_unit,
+                    memory(mem_used, &format.mem_used),
+                ],
+                width,
+                Align::Left,
+            ),
+            columns(
+                &[
+                    format.cpu_usage.format(cpu_usage_cur_value) + usage_unit,
+                    format.gpu_usage.format(gpu_usage_cur_value) + usage_unit,
+                    memory(mem_free, &format.mem_free),
+                ],
+                width,
+                Align::Left,
+            ),
+        ]
+    } else {
+        // Horizontal
+        vec![
+            columns(
+                &[
+                    String::from("CPU"),
+                    format.cpu_temp.format(cpu_temp_cur_value) + temp_unit,
+                    format.cpu_usage.format(cpu_usage_cur_value) + usage_unit,
+                ],
+                width,
+                Align::Left,
+            ),
+            columns(
+                &[
+                    String::from("GPU"),
+                    format.gpu_temp.format(gpu_temp_cur_value) + temp_unit,
+                    format.gpu_usage.format(gpu_usage_cur_value) + usage_unit,
+                ],
+                width,
+                Align::Left,
+            ),
+            columns(
+                &[
+                    String::from("MEM"),
+                    memory(mem_used, &format.mem_used),
+                    format.mem_load.format(mem_load) + usage_unit,
+                    // mem_free, mem_unit.to_lowercase()
+                ],
+                width,
+                Align::Left,
+            ),
+        ]
+    };
+    Ok(lines)
+}
+
+/// Has `hwinfo` keep the previous value of every `rate_k` reading of `sections`, the custom
+/// pages and `[Lighting]`.
+pub fn track_counters(
+    sections: &[&ini::Properties],
+    hwinfo: &mut Hwinfo,
+) -> Result<(), anyhow::Error> {
+    for page in sections {
+        for k in 0..CUSTOM_SENSORS {
+            if sensor_counter(page, k)?.is_none() {
+                continue;
+            }
+            let sensor = match page.get(format!("sensor_{}", k)) {
+                Some(sensor) => sensor.split(";").collect::<Vec<&str>>(),
+                None => continue,
+            };
+            if sensor.len() == 2 && sensor[0] != "FILE" && sensor[0] != "EXPR" {
+                hwinfo.track_counter(sensor[0], sensor[1]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects the `Sensor;Reading` pairs used by the custom pages.
+pub fn page_readings(pages: &[&ini::Properties]) -> Vec<(String, String)> {
+    let mut readings = Vec::new();
+    for page in pages {
+        for k in 0..CUSTOM_SENSORS {
+            let sensor = match page.get(format!("sensor_{}", k)) {
+                Some(sensor) => sensor.split(";").collect::<Vec<&str>>(),
+                None => continue,
+            };
+            if sensor.len() < 2 || sensor[0] == "FILE" || sensor[0] == "EXPR" {
+                continue;
+            }
+            let pair = (sensor[0].to_string(), sensor[1].to_string());
+            if !readings.contains(&pair) {
+                readings.push(pair);
+            }
+        }
+    }
+    readings
 }
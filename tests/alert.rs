@@ -0,0 +1,141 @@
+use hwinfo_steelseries_oled::alert::{
+    alert_lines, Alert, AlertSettings, AlertState, Severity, Thresholds,
+};
+
+const GPU: Thresholds = Thresholds {
+    warn: Some(80.0),
+    crit: Some(90.0),
+};
+
+/// Feeds `values` one tick at a time and collects the severity shown after each.
+fn run(state: &mut AlertState, settings: &AlertSettings, values: &[f64]) -> Vec<Option<Severity>> {
+    values
+        .iter()
+        .map(|value| {
+            state.update(&GPU, *value, settings);
+            state.shown()
+        })
+        .collect()
+}
+
+#[test]
+fn rising_thresholds_raise_at_or_above() {
+    let thresholds = Thresholds {
+        warn: Some(80.0),
+        crit: Some(90.0),
+    };
+    assert_eq!(thresholds.severity(79.9), None);
+    assert_eq!(thresholds.severity(80.0), Some(Severity::Warning));
+    assert_eq!(thresholds.severity(89.0), Some(Severity::Warning));
+    assert_eq!(thresholds.severity(95.0), Some(Severity::Critical));
+    assert_eq!(thresholds.severity(f64::NAN), None);
+}
+
+#[test]
+fn crit_below_warn_alerts_on_low_readings() {
+    let thresholds = Thresholds {
+        warn: Some(600.0),
+        crit: Some(300.0),
+    };
+    assert_eq!(thresholds.severity(1200.0), None);
+    assert_eq!(thresholds.severity(500.0), Some(Severity::Warning));
+    assert_eq!(thresholds.severity(0.0), Some(Severity::Critical));
+}
+
+#[test]
+fn single_threshold() {
+    let crit_only = Thresholds {
+        warn: None,
+        crit: Some(90.0),
+    };
+    assert_eq!(crit_only.severity(85.0), None);
+    assert_eq!(crit_only.severity(90.0), Some(Severity::Critical));
+    assert!(Thresholds::default().is_empty());
+    assert_eq!(Thresholds::default().severity(1000.0), None);
+}
+
+#[test]
+fn alert_lines_lead_with_the_worst() {
+    let alerts = vec![
+        Alert {
+            severity: Severity::Warning,
+            text: String::from("CPU 82°"),
+        },
+        Alert {
+            severity: Severity::Critical,
+            text: String::from("GPU 93°"),
+        },
+        Alert {
+            severity: Severity::Warning,
+            text: String::from("VRM 85°"),
+        },
+    ];
+    assert_eq!(
+        alert_lines(&alerts, 3),
+        vec!["CRITICAL", "GPU 93°", "CPU 82°"]
+    );
+    assert_eq!(alert_lines(&alerts, 2), vec!["CRITICAL", "GPU 93°"]);
+    assert!(alert_lines(&[], 3).is_empty());
+}
+
+#[test]
+fn hysteresis_holds_the_alert_near_the_threshold() {
+    let settings = AlertSettings {
+        hysteresis: 3.0,
+        ..AlertSettings::default()
+    };
+    let mut state = AlertState::default();
+    let shown = run(&mut state, &settings, &[81.0, 79.0, 80.5, 77.5, 76.0, 79.0]);
+    let warning = Some(Severity::Warning);
+    assert_eq!(shown, vec![warning, warning, warning, warning, None, None]);
+
+    let shown = run(&mut state, &settings, &[91.0, 88.0, 86.0]);
+    assert_eq!(
+        shown,
+        vec![Some(Severity::Critical), Some(Severity::Critical), warning]
+    );
+}
+
+#[test]
+fn delay_ignores_short_spikes() {
+    let settings = AlertSettings {
+        delay: 2,
+        ..AlertSettings::default()
+    };
+    let mut state = AlertState::default();
+    let shown = run(&mut state, &settings, &[85.0, 85.0, 70.0, 85.0, 85.0, 85.0]);
+    assert_eq!(
+        shown,
+        vec![None, None, None, None, None, Some(Severity::Warning)]
+    );
+}
+
+#[test]
+fn cooldown_keeps_a_cleared_alert_down() {
+    let settings = AlertSettings {
+        cooldown: 2,
+        ..AlertSettings::default()
+    };
+    let mut state = AlertState::default();
+    let shown = run(&mut state, &settings, &[85.0, 70.0, 85.0, 85.0, 85.0]);
+    let warning = Some(Severity::Warning);
+    assert_eq!(shown, vec![warning, None, None, warning, warning]);
+}
+
+#[test]
+fn acknowledged_alerts_stay_silent_until_they_clear_or_worsen() {
+    let settings = AlertSettings::default();
+    let mut state = AlertState::default();
+    run(&mut state, &settings, &[85.0]);
+    state.acknowledge();
+    assert_eq!(state.shown(), None);
+    assert_eq!(run(&mut state, &settings, &[86.0]), vec![None]);
+    assert_eq!(
+        run(&mut state, &settings, &[95.0]),
+        vec![Some(Severity::Critical)]
+    );
+
+    state.acknowledge();
+    let shown = run(&mut state, &settings, &[95.0, 70.0, 85.0]);
+    assert_eq!(shown, vec![None, None, Some(Severity::Warning)]);
+}
@@ -0,0 +1,176 @@
+//! Golden tests of the 1-bit framebuffer behind the `render=bitmap` pages.
+
+use hwinfo_steelseries_oled::bitmap::{BitmapIcon, Framebuffer};
+use hwinfo_steelseries_oled::display::Frame;
+use hwinfo_steelseries_oled::sparkline::{SampleBuffer, SparklineStyle};
+use serde_json::json;
+
+fn golden(rows: &[&str]) -> String {
+    rows.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+#[test]
+fn draws_text_with_bundled_font() {
+    let mut framebuffer = Framebuffer::new(12, 7);
+    let cursor = framebuffer.draw_text(0, 0, "42");
+
+    assert_eq!(cursor, 12);
+    assert_eq!(Framebuffer::text_width("42"), 11);
+    assert_eq!(
+        framebuffer.to_ascii(),
+        golden(&[
+            "...#...###..",
+            "..##..#...#.",
+            ".#.#......#.",
+            "#..#.....#..",
+            "#####...#...",
+            "...#...#....",
+            "...#..#####.",
+        ])
+    );
+}
+
+#[test]
+fn draws_outlined_bar_filled_to_fraction() {
+    let mut framebuffer = Framebuffer::new(10, 4);
+    framebuffer.draw_bar(0, 0, 10, 4, 0.5);
+
+    assert_eq!(
+        framebuffer.to_ascii(),
+        golden(&["##########", "#####....#", "#####....#", "##########"])
+    );
+
+    // Out of range and NaN readings must not overflow the outline.
+    let mut full = Framebuffer::new(10, 4);
+    full.draw_bar(0, 0, 10, 4, 7.0);
+    let mut empty = Framebuffer::new(10, 4);
+    empty.draw_bar(0, 0, 10, 4, f64::NAN);
+    assert_eq!(full.to_ascii(), golden(&["##########"; 4]));
+    assert_eq!(
+        empty.to_ascii(),
+        golden(&["##########", "#........#", "#........#", "##########"])
+    );
+}
+
+#[test]
+fn draws_icons() {
+    let mut framebuffer = Framebuffer::new(7, 7);
+    let cursor = framebuffer.draw_icon(0, 0, BitmapIcon::Cpu);
+
+    assert_eq!(cursor, 8);
+    assert_eq!(
+        framebuffer.to_ascii(),
+        golden(&[".#.#.#.", "#######", "##...##", "##.#.##", "##...##", "#######", ".#.#.#.",])
+    );
+    assert_eq!(BitmapIcon::from_name("MEM"), Some(BitmapIcon::Ram));
+    assert_eq!(BitmapIcon::from_name("unknown"), None);
+}
+
+fn samples(values: &[f64]) -> SampleBuffer {
+    let mut buffer = SampleBuffer::new(values.len());
+    for value in values {
+        buffer.push(*value);
+    }
+    buffer
+}
+
+#[test]
+fn sample_buffer_keeps_the_latest_samples() {
+    let mut buffer = SampleBuffer::new(3);
+    assert_eq!(buffer.range(), None);
+    for value in [1.0, 5.0, 2.0, 4.0] {
+        buffer.push(value);
+    }
+
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(
+        buffer.samples().copied().collect::<Vec<f64>>(),
+        vec![5.0, 2.0, 4.0]
+    );
+    assert_eq!(buffer.range(), Some((2.0, 5.0)));
+}
+
+#[test]
+fn draws_line_sparkline() {
+    let mut framebuffer = Framebuffer::new(5, 4);
+    framebuffer.draw_sparkline(
+        0,
+        0,
+        5,
+        4,
+        &samples(&[0.0, 1.0, 3.0, 3.0, 0.0]),
+        (0.0, 3.0),
+        SparklineStyle::Line,
+    );
+
+    assert_eq!(
+        framebuffer.to_ascii(),
+        golden(&["..###", "..#.#", ".##.#", "##..#"])
+    );
+}
+
+#[test]
+fn draws_bar_sparkline_right_aligned() {
+    let mut framebuffer = Framebuffer::new(6, 3);
+    framebuffer.draw_sparkline(
+        0,
+        0,
+        6,
+        3,
+        &samples(&[2.0, f64::NAN, 0.0, 1.0]),
+        (0.0, 2.0),
+        SparklineStyle::Bars,
+    );
+
+    assert_eq!(
+        framebuffer.to_ascii(),
+        golden(&["..#...", "..#..#", "..#.##"])
+    );
+}
+
+#[test]
+fn sparkline_drops_samples_that_do_not_fit() {
+    let mut framebuffer = Framebuffer::new(2, 2);
+    framebuffer.draw_sparkline(
+        0,
+        0,
+        2,
+        2,
+        &samples(&[1.0, 1.0, 0.0, 1.0]),
+        (0.0, 1.0),
+        SparklineStyle::Bars,
+    );
+
+    assert_eq!(framebuffer.to_ascii(), golden(&[".#", "##"]));
+}
+
+#[test]
+fn clips_drawing_outside_the_screen() {
+    let mut framebuffer = Framebuffer::new(4, 2);
+    framebuffer.draw_text(2, 0, "88");
+    framebuffer.fill_rect(3, 1, 10, 10, true);
+
+    assert_eq!(framebuffer.to_ascii(), golden(&["...#", "..##"]));
+}
+
+#[test]
+fn packs_image_data_row_major_msb_first() {
+    let mut framebuffer = Framebuffer::new(10, 2);
+    framebuffer.set(0, 0, true);
+    framebuffer.set(9, 0, true);
+    framebuffer.set(7, 1, true);
+
+    assert_eq!(framebuffer.to_image_data(), vec![0x80, 0x40, 0x01, 0x00]);
+}
+
+#[test]
+fn image_frames_use_sized_image_data_key() {
+    let mut framebuffer = Framebuffer::new(128, 40);
+    framebuffer.invert();
+    let frame = Frame::page(1, Vec::new()).with_image(framebuffer);
+
+    let value = frame.to_value();
+    let data = value["image-data-128x40"].as_array().unwrap();
+    assert_eq!(data.len(), 128 / 8 * 40);
+    assert!(data.iter().all(|byte| byte == &json!(255)));
+}
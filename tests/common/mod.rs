@@ -0,0 +1,233 @@
+//! A small in-process fake of the SteelSeries GG GameSense HTTP API.
+//!
+//! `MockGameSense::start` listens on a random local port and writes a `coreProps.json`
+//! pointing at it, laid out like `%PROGRAMDATA%/SteelSeries/SteelSeries Engine 3/`.
+//! Every request is recorded so tests can assert on what the app posted.
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+const ENDPOINTS: [&str; 7] = [
+    "game_metadata",
+    "bind_game_event",
+    "register_game_event",
+    "game_event",
+    "game_heartbeat",
+    "remove_game",
+    "remove_game_event",
+];
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub endpoint: String,
+    pub body: Value,
+}
+
+pub struct MockGameSense {
+    pub address: String,
+    pub program_data: PathBuf,
+    requests: Arc<Mutex<Vec<Request>>>,
+    /// Events answered with a GameSense `{"error": ..}` instead of being accepted.
+    rejected: Arc<Mutex<Vec<String>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl MockGameSense {
+    pub fn start() -> MockGameSense {
+        let program_data = std::env::temp_dir().join(format!(
+            "hwinfo-steelseries-mock-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        MockGameSense::start_at(program_data)
+    }
+
+    /// Starts on a fresh port, overwriting `coreProps.json` under `program_data` the way
+    /// GG does when it restarts.
+    pub fn start_at(program_data: PathBuf) -> MockGameSense {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let core_props_dir = program_data
+            .join("SteelSeries")
+            .join("SteelSeries Engine 3");
+        std::fs::create_dir_all(&core_props_dir).unwrap();
+        std::fs::write(
+            core_props_dir.join("coreProps.json"),
+            json!({ "address": address, "encryptedAddress": "" }).to_string(),
+        )
+        .unwrap();
+
+        listener.set_nonblocking(true).unwrap();
+        let thread_requests = Arc::clone(&requests);
+        let thread_rejected = Arc::clone(&rejected);
+        let thread_running = Arc::clone(&running);
+        thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let requests = Arc::clone(&thread_requests);
+                        let rejected = Arc::clone(&thread_rejected);
+                        let running = Arc::clone(&thread_running);
+                        thread::spawn(move || serve(stream, requests, rejected, running));
+                    }
+                    Err(_) => thread::sleep(Duration::from_millis(10)),
+                }
+            }
+        });
+
+        MockGameSense {
+            address,
+            program_data,
+            requests,
+            rejected,
+            running,
+        }
+    }
+
+    /// Points the GameSense discovery (`%PROGRAMDATA%`) at this mock.
+    pub fn install(&self) {
+        std::env::set_var("PROGRAMDATA", &self.program_data);
+    }
+
+    pub fn core_props(&self) -> PathBuf {
+        self.program_data
+            .join("SteelSeries")
+            .join("SteelSeries Engine 3")
+            .join("coreProps.json")
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn requests_to(&self, endpoint: &str) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.endpoint == endpoint)
+            .map(|request| request.body)
+            .collect()
+    }
+
+    /// Waits until at least `count` requests hit `endpoint`.
+    pub fn wait_for(&self, endpoint: &str, count: usize) -> Vec<Value> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let requests = self.requests_to(endpoint);
+            if requests.len() >= count || Instant::now() > deadline {
+                return requests;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Answers bindings and events named `event` with an error, like GG does for an invalid
+    /// handler.
+    pub fn reject(&self, event: &str) {
+        self.rejected.lock().unwrap().push(event.to_string());
+    }
+
+    /// Stops accepting requests, like GG being closed.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for MockGameSense {
+    fn drop(&mut self) {
+        self.stop();
+        std::fs::remove_dir_all(&self.program_data).ok();
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    requests: Arc<Mutex<Vec<Request>>>,
+    rejected: Arc<Mutex<Vec<String>>>,
+    running: Arc<AtomicBool>,
+) {
+    stream
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    // reqwest keeps connections alive, so handle requests until the peer hangs up
+    while running.load(Ordering::Relaxed) {
+        let mut request_line = String::new();
+        match reader.read_line(&mut request_line) {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(_) => continue,
+        }
+        let path = request_line
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or("/")
+            .trim_start_matches('/')
+            .to_string();
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                return;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        if !running.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let (status, response) = if ENDPOINTS.contains(&path.as_str()) {
+            let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let event = body["event"].as_str().unwrap_or("").to_string();
+            requests.lock().unwrap().push(Request {
+                endpoint: path.clone(),
+                body,
+            });
+            if rejected.lock().unwrap().contains(&event) {
+                (
+                    "400 Bad Request",
+                    json!({ "error": format!("Invalid event {}", event) }).to_string(),
+                )
+            } else {
+                ("200 OK", json!({ path: "ok" }).to_string())
+            }
+        } else {
+            ("404 Not Found", String::from("Page not found"))
+        };
+        let reply = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            response.len(),
+            response
+        );
+        if writer.write_all(reply.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use hwinfo_steelseries_oled::counter::Counter;
+
+#[test]
+fn rate_per_second() {
+    let counter = Counter::default();
+    assert_eq!(counter.rate(1000.0, 1050.0, 2.0), Some(25.0));
+    assert_eq!(counter.rate(1000.0, 1000.0, 1.0), Some(0.0));
+}
+
+#[test]
+fn resets_and_missing_time_give_nothing() {
+    let counter = Counter::default();
+    assert_eq!(counter.rate(1000.0, 10.0, 1.0), None);
+    assert_eq!(counter.rate(1000.0, 1050.0, 0.0), None);
+    assert_eq!(counter.rate(f64::NAN, 1050.0, 1.0), None);
+}
+
+#[test]
+fn wraparound() {
+    let counter = Counter {
+        wrap: Some(4294967296.0),
+    };
+    assert_eq!(counter.rate(4294967196.0, 100.0, 2.0), Some(100.0));
+    // Past the wrap can't be a roll over
+    let small = Counter { wrap: Some(500.0) };
+    assert_eq!(small.rate(1000.0, 10.0, 1.0), None);
+}
@@ -0,0 +1,72 @@
+use hwinfo_steelseries_oled::expr::{glob_match, Expr};
+
+const READINGS: [(&str, &str, f64); 5] = [
+    ("CPU [#0]: Ryzen", "CPU Package Power", 88.5),
+    ("GPU [#0]: RTX 3090", "GPU Power", 310.0),
+    ("Memory", "Physical Memory Used", 12288.0),
+    ("Memory", "Physical Memory Total", 32768.0),
+    ("CPU [#0]: Ryzen", "Core 0 Temp", 61.0),
+];
+
+fn eval(expression: &str) -> Result<f64, anyhow::Error> {
+    let mut readings = READINGS.to_vec();
+    readings.push(("CPU [#0]: Ryzen", "Core 1 Temp", 67.0));
+    Expr::parse(expression)?.eval(&|sensor, reading| {
+        readings
+            .iter()
+            .filter(|(s, r, _)| glob_match(sensor, s) && glob_match(reading, r))
+            .map(|(_, _, value)| *value)
+            .collect()
+    })
+}
+
+#[test]
+fn arithmetic_follows_precedence() {
+    assert_eq!(eval("1 + 2 * 3").unwrap(), 7.0);
+    assert_eq!(eval("(1 + 2) * 3").unwrap(), 9.0);
+    assert_eq!(eval("10 - 4 - 3").unwrap(), 3.0);
+    assert_eq!(eval("-2 * -(3 - 5)").unwrap(), -4.0);
+    assert_eq!(eval("7 / 2").unwrap(), 3.5);
+}
+
+#[test]
+fn readings_and_functions() {
+    assert_eq!(
+        eval("{CPU [#0]: Ryzen;CPU Package Power} + {GPU [#0]: RTX 3090;GPU Power}").unwrap(),
+        398.5
+    );
+    assert_eq!(
+        eval("{Memory;Physical Memory Used} / {Memory;Physical Memory Total} * 100").unwrap(),
+        37.5
+    );
+    assert_eq!(eval("max({CPU*;Core * Temp})").unwrap(), 67.0);
+    assert_eq!(eval("min({CPU*;Core * Temp}, 50)").unwrap(), 50.0);
+    assert_eq!(eval("avg({CPU*;Core * Temp})").unwrap(), 64.0);
+    assert_eq!(eval("sum({*;*Power})").unwrap(), 398.5);
+    assert_eq!(eval("abs(2 - 5)").unwrap(), 3.0);
+}
+
+#[test]
+fn errors_name_the_problem() {
+    let error = |expression: &str| eval(expression).unwrap_err().to_string();
+    assert!(error("{Memory;Swap}").contains("not found"));
+    assert!(error("{CPU*;Core * Temp}").contains("several readings"));
+    assert!(error("median(1, 2)").contains("Unknown function"));
+    assert!(error("1 +").contains("ends too early"));
+    assert!(error("(1 + 2").contains("\")\""));
+    assert!(error("1 2").contains("Unexpected"));
+    assert!(error("{Memory}").contains("Sensor;Reading"));
+}
+
+#[test]
+fn glob_patterns() {
+    assert!(glob_match("GPU Power", "GPU Power"));
+    assert!(!glob_match("GPU Power", "GPU Power 2"));
+    assert!(glob_match("Core * Temp", "Core 12 Temp"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("CPU*", "CPU [#0]: Ryzen"));
+    assert!(glob_match("*Power", "CPU Package Power"));
+    assert!(glob_match("a*b*c", "a_b_b_c"));
+    assert!(!glob_match("ab*ba", "aba"));
+    assert!(!glob_match("Core * Temp", "Core 0 Clock"));
+}
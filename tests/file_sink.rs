@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+
+use hwinfo_steelseries_oled::alert::Severity;
+use hwinfo_steelseries_oled::bitmap::Framebuffer;
+use hwinfo_steelseries_oled::display::{DisplaySink, FileSink, Frame};
+
+/// Fresh output file for one test.
+fn frame_path(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("hwinfo-file-sink-{}.txt", test));
+    fs::remove_file(&path).ok();
+    path
+}
+
+fn lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+/// Lines of every screen in the file, screens being separated by a blank line.
+fn read_screens(path: &PathBuf) -> Vec<Vec<String>> {
+    let contents = fs::read_to_string(path).unwrap();
+    contents
+        .strip_suffix('\n')
+        .unwrap()
+        .split("\n\n")
+        .map(|screen| screen.lines().map(String::from).collect())
+        .collect()
+}
+
+#[test]
+fn writes_the_frame_lines() {
+    let path = frame_path("lines");
+    let mut sink = FileSink::new(path.clone());
+    let frame = Frame::page(1, lines(&["CPU 45°", "GPU 60°", "RAM 23%"]));
+    sink.show(&frame).unwrap();
+    assert_eq!(read_screens(&path), vec![frame.lines]);
+}
+
+#[test]
+fn overwrites_with_the_latest_frame() {
+    let path = frame_path("latest");
+    let mut sink = FileSink::new(path.clone());
+    sink.show(&Frame::page(1, lines(&["page 1", "a", "b"])))
+        .unwrap();
+    sink.show(&Frame::status(lines(&["Disconnected", "FROM", "HWiNFO"])))
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "Disconnected\nFROM\nHWiNFO\n"
+    );
+    sink.show(&Frame::alert(
+        Severity::Critical,
+        lines(&["CRITICAL", "GPU 93°"]),
+    ))
+    .unwrap();
+    assert_eq!(read_screens(&path), vec![lines(&["CRITICAL", "GPU 93°"])]);
+}
+
+#[test]
+fn keeps_every_screen() {
+    let path = frame_path("screens");
+    let mut sink = FileSink::new(path.clone());
+    let keyboard = Frame::page(1, lines(&["CPU 45°", "GPU 60°"]));
+    let headset = Frame::page(2, lines(&["NET 12M/s", "DL 1.2G", "UP 30K"])).on_screen(1);
+    sink.show(&keyboard).unwrap();
+    sink.show(&headset).unwrap();
+    assert_eq!(
+        read_screens(&path),
+        vec![keyboard.lines.clone(), headset.lines.clone()]
+    );
+
+    // Updating one screen leaves the other as it was
+    let keyboard = Frame::page(2, lines(&["VRM 70°", "FAN 1200"]));
+    sink.show(&keyboard).unwrap();
+    assert_eq!(read_screens(&path), vec![keyboard.lines, headset.lines]);
+}
+
+#[test]
+fn bitmap_pages_write_their_text() {
+    let path = frame_path("bitmap");
+    let mut sink = FileSink::new(path.clone());
+    let frame = Frame::page(1, lines(&["CPU 45°", "GPU 60°", "RAM 23%"]))
+        .with_image(Framebuffer::new(128, 40));
+    sink.show(&frame).unwrap();
+    assert_eq!(read_screens(&path), vec![frame.lines]);
+}
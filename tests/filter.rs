@@ -0,0 +1,60 @@
+use hwinfo_steelseries_oled::filter::{Filter, FilterState};
+
+/// Feeds `values` one tick at a time and collects the filtered value after each.
+fn run(filter: &str, values: &[f64]) -> Vec<Option<f64>> {
+    let mut state = FilterState::new(Filter::parse(filter).unwrap());
+    values.iter().map(|value| state.push(*value)).collect()
+}
+
+#[test]
+fn parses_filters() {
+    assert_eq!(Filter::parse("avg:5").unwrap(), Filter::Average(5));
+    assert_eq!(Filter::parse(" EMA : 0.3 ").unwrap(), Filter::Exponential(0.3));
+    assert_eq!(Filter::parse("median:3").unwrap(), Filter::Median(3));
+    assert_eq!(Filter::parse("peak:0.1").unwrap(), Filter::PeakHold(0.1));
+    assert!(Filter::parse("avg").is_err());
+    assert!(Filter::parse("avg:0").is_err());
+    assert!(Filter::parse("ema:1.5").is_err());
+    assert!(Filter::parse("mean:3").is_err());
+}
+
+#[test]
+fn moving_average_over_the_window() {
+    assert_eq!(
+        run("avg:3", &[3.0, 6.0, 9.0, 30.0]),
+        vec![Some(3.0), Some(4.5), Some(6.0), Some(15.0)]
+    );
+}
+
+#[test]
+fn exponential_smoothing() {
+    assert_eq!(
+        run("ema:0.5", &[10.0, 20.0, 20.0]),
+        vec![Some(10.0), Some(15.0), Some(17.5)]
+    );
+}
+
+#[test]
+fn median_ignores_spikes() {
+    assert_eq!(
+        run("median:3", &[10.0, 100.0, 12.0, 11.0]),
+        vec![Some(10.0), Some(55.0), Some(12.0), Some(12.0)]
+    );
+}
+
+#[test]
+fn peak_hold_decays_towards_the_reading() {
+    assert_eq!(
+        run("peak:0.5", &[10.0, 90.0, 10.0, 10.0, 95.0]),
+        vec![Some(10.0), Some(90.0), Some(50.0), Some(30.0), Some(95.0)]
+    );
+}
+
+#[test]
+fn missing_readings_keep_the_last_value() {
+    assert_eq!(run("avg:2", &[f64::NAN]), vec![None]);
+    assert_eq!(
+        run("avg:2", &[4.0, f64::NAN, 8.0]),
+        vec![Some(4.0), Some(4.0), Some(6.0)]
+    );
+}
@@ -0,0 +1,39 @@
+use hwinfo_steelseries_oled::format::NumberFormat;
+
+fn format(spec: &str, value: f64) -> String {
+    NumberFormat::parse(spec).unwrap().format(value)
+}
+
+#[test]
+fn printf_and_rust_specs() {
+    assert_eq!(format("%.3f", 1.25), "1.250");
+    assert_eq!(format("{:02.0}", 7.0), "07");
+    assert_eq!(format(".1", 45.06), "45.1");
+    assert_eq!(format("%5.1f", 3.0), "  3.0");
+    assert_eq!(format("", 2.5), "2.5");
+}
+
+#[test]
+fn sign_and_zero_padding() {
+    assert_eq!(format("%+.1f", 2.5), "+2.5");
+    assert_eq!(format("+.1", -2.5), "-2.5");
+    assert_eq!(format("+05.1", 2.5), "+02.5");
+    assert_eq!(format("05.1", -2.5), "-02.5");
+    // Rounds to zero without a stray minus
+    assert_eq!(format(".0", -0.2), "0");
+}
+
+#[test]
+fn thousands_separators() {
+    assert_eq!(format(",.0", 12345.0), "12,345");
+    assert_eq!(format("',.2", 1234567.891), "1,234,567.89");
+    assert_eq!(format(",.0", -999.0), "-999");
+    assert_eq!(format(",.0", f64::INFINITY), "inf");
+}
+
+#[test]
+fn invalid_specs() {
+    assert!(NumberFormat::parse("{:.1").is_err());
+    assert!(NumberFormat::parse(".x").is_err());
+    assert!(NumberFormat::parse("abc").is_err());
+}
@@ -0,0 +1,590 @@
+//! End-to-end tests of the GameSense output against the mock GG server in `tests/common`.
+
+mod common;
+
+use std::sync::Mutex;
+
+use common::MockGameSense;
+use hwinfo_steelseries_oled::alert::Severity;
+use hwinfo_steelseries_oled::client::{core_props_path, GameMetadata, SteelSeriesClient};
+use hwinfo_steelseries_oled::device::{DeviceProfile, DEFAULT_PROFILE};
+use hwinfo_steelseries_oled::display::{
+    DisplaySink, Frame, GameSenseSink, LineKind, PageRender, ScreenLayout, ALERT_COLOR_EVENT,
+};
+use hwinfo_steelseries_oled::lighting::{AlertEffects, ColorGradient, Rgb, Zone};
+use serde_json::json;
+
+// GameSense discovery reads %PROGRAMDATA%, so tests touching it can't run concurrently.
+static PROGRAM_DATA: Mutex<()> = Mutex::new(());
+
+fn metadata() -> GameMetadata {
+    GameMetadata {
+        game: String::from("HWINFO"),
+        game_display_name: String::from("HWiNFO_Stats"),
+        developer: String::from("Ryan"),
+        icon_color_id: None,
+        deinitialize_timer_length_ms: None,
+    }
+}
+
+fn sink(mock: &MockGameSense, pages: usize) -> GameSenseSink {
+    let device = DeviceProfile::from_name(DEFAULT_PROFILE).unwrap();
+    device_sink(mock, &device, &vec![PageRender::text(device.lines); pages])
+}
+
+fn device_sink(
+    mock: &MockGameSense,
+    device: &DeviceProfile,
+    pages: &[PageRender],
+) -> GameSenseSink {
+    mock.install();
+    let mut client = SteelSeriesClient::new(metadata(), core_props_path().unwrap());
+    client.connect().unwrap();
+    let layout = ScreenLayout {
+        device: *device,
+        pages: pages.to_vec(),
+    };
+    GameSenseSink::new(client, &[layout]).unwrap()
+}
+
+#[test]
+fn registers_game_and_binds_page_events() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let _sink = sink(&mock, 2);
+
+    let metadata = mock.requests_to("game_metadata");
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata[0]["game"], "HWINFO");
+    assert_eq!(metadata[0]["game_display_name"], "HWiNFO_Stats");
+
+    let events = mock
+        .requests_to("bind_game_event")
+        .iter()
+        .map(|bind| bind["event"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(events, vec!["PAGE1", "PAGE2", "ERROR", "ALERT"]);
+
+    let bind = &mock.requests_to("bind_game_event")[0];
+    let handler = &bind["handlers"][0];
+    assert_eq!(handler["device-type"], "screened");
+    assert_eq!(handler["zone"], "one");
+    assert_eq!(handler["mode"], "screen");
+    let lines = handler["datas"][0]["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["context-frame-key"], "line1");
+    assert_eq!(lines[2]["context-frame-key"], "line3");
+
+    assert!(!mock.wait_for("game_heartbeat", 1).is_empty());
+}
+
+#[test]
+fn render_loop_posts_frames_per_page() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 2);
+
+    // What the main loop does each tick: render lines for the current page and show them.
+    let frames = [
+        Frame::page(
+            1,
+            vec![
+                "CPU 45° 10%".into(),
+                "GPU 40° 3%".into(),
+                "MEM 12G 38%".into(),
+            ],
+        ),
+        Frame::page(
+            1,
+            vec![
+                "CPU 46° 12%".into(),
+                "GPU 40° 4%".into(),
+                "MEM 12G 38%".into(),
+            ],
+        ),
+        Frame::page(
+            2,
+            vec!["RAM 15g".into(), "NET ▲ 01k/s".into(), "NET ▼ 00k/s".into()],
+        ),
+        Frame::status(vec!["Disconnected".into(), "FROM".into(), "HWiNFO".into()]),
+    ];
+    for frame in frames.iter() {
+        sink.show(frame).unwrap();
+    }
+
+    let posted = mock.requests_to("game_event");
+    assert_eq!(posted.len(), frames.len());
+    for (i, (event, frame)) in posted.iter().zip(frames.iter()).enumerate() {
+        assert_eq!(event["game"], "HWINFO");
+        assert_eq!(event["event"], frame.event());
+        assert_eq!(event["data"]["value"], i);
+        assert_eq!(event["data"]["frame"], frame.to_value());
+    }
+    assert_eq!(posted[2]["event"], "PAGE2");
+    assert_eq!(
+        posted[2]["data"]["frame"],
+        json!({"line1": "RAM 15g", "line2": "NET ▲ 01k/s", "line3": "NET ▼ 00k/s"})
+    );
+    assert_eq!(posted[3]["event"], "ERROR");
+}
+
+#[test]
+fn progress_bar_lines_bind_native_bars_and_send_percent() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let render = PageRender::Text {
+        lines: vec![LineKind::Text, LineKind::ProgressBar, LineKind::Text],
+    };
+    let device = DeviceProfile::from_name(DEFAULT_PROFILE).unwrap();
+    let mut sink = device_sink(&mock, &device, &[render]);
+
+    let bind = &mock.requests_to("bind_game_event")[0];
+    let lines = bind["handlers"][0]["datas"][0]["lines"].as_array().unwrap();
+    assert_eq!(lines[0]["has-text"], true);
+    assert_eq!(lines[1]["has-progress-bar"], true);
+    assert_eq!(lines[1]["context-frame-key"], "line2");
+
+    let frame = Frame::page(
+        1,
+        vec!["GPU 57%".into(), "GPU 57%".into(), "VRAM 8g".into()],
+    )
+    .with_bars(vec![None, Some(0.567), None]);
+    sink.show(&frame).unwrap();
+
+    let posted = mock.wait_for("game_event", 1);
+    assert_eq!(
+        posted[0]["data"]["frame"],
+        json!({"line1": "GPU 57%", "line2": 57, "line3": "VRAM 8g"})
+    );
+}
+
+#[test]
+fn device_profile_sets_device_type_and_line_count() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let device = DeviceProfile::from_name("screened-128x40").unwrap();
+    assert_eq!(device.lines, 2);
+    let pages = [
+        PageRender::text(device.lines),
+        PageRender::Bitmap {
+            width: device.width,
+            height: device.height,
+        },
+    ];
+    let _sink = device_sink(&mock, &device, &pages);
+
+    let binds = mock.requests_to("bind_game_event");
+    assert_eq!(binds.len(), 4);
+    for bind in [&binds[0], &binds[2], &binds[3]] {
+        let handler = &bind["handlers"][0];
+        assert_eq!(handler["device-type"], "screened-128x40");
+        assert_eq!(handler["datas"][0]["lines"].as_array().unwrap().len(), 2);
+    }
+    assert_eq!(binds[1]["handlers"][0]["device-type"], "screened-128x40");
+    assert!(DeviceProfile::from_name("screened-64x16").is_none());
+}
+
+#[test]
+fn screens_get_separate_events_and_handlers() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    mock.install();
+    let mut client = SteelSeriesClient::new(metadata(), core_props_path().unwrap());
+    client.connect().unwrap();
+    let keyboard = DeviceProfile::from_name("screened-128x40").unwrap();
+    let headset = DeviceProfile::from_name("screened-128x52").unwrap();
+    let layouts = [
+        ScreenLayout {
+            device: keyboard,
+            pages: vec![PageRender::text(keyboard.lines)],
+        },
+        ScreenLayout {
+            device: headset,
+            pages: vec![PageRender::text(headset.lines); 2],
+        },
+    ];
+    let mut sink = GameSenseSink::new(client, &layouts).unwrap();
+
+    let binds = mock.requests_to("bind_game_event");
+    let events = binds
+        .iter()
+        .map(|bind| bind["event"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        events,
+        vec![
+            "PAGE1",
+            "ERROR",
+            "ALERT",
+            "SCREEN2_PAGE1",
+            "SCREEN2_PAGE2",
+            "SCREEN2_ERROR",
+            "SCREEN2_ALERT"
+        ]
+    );
+    assert_eq!(binds[0]["handlers"][0]["device-type"], "screened-128x40");
+    assert_eq!(binds[3]["handlers"][0]["device-type"], "screened-128x52");
+    assert_eq!(
+        binds[3]["handlers"][0]["datas"][0]["lines"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+
+    let frame = Frame::page(2, vec!["a".into(), "b".into(), "c".into()]).on_screen(1);
+    sink.show(&frame).unwrap();
+    assert_eq!(mock.wait_for("game_event", 1)[0]["event"], "SCREEN2_PAGE2");
+
+    sink.shutdown().unwrap();
+    let goodbyes = mock
+        .requests_to("game_event")
+        .iter()
+        .skip(1)
+        .map(|event| event["event"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(goodbyes, vec!["ERROR", "SCREEN2_ERROR"]);
+}
+
+#[test]
+fn alert_frames_go_to_the_bold_alert_event() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+
+    let bind = mock
+        .requests_to("bind_game_event")
+        .into_iter()
+        .find(|bind| bind["event"] == "ALERT")
+        .unwrap();
+    let lines = bind["handlers"][0]["datas"][0]["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line["bold"] == true));
+
+    let frame = Frame::alert(
+        Severity::Critical,
+        vec!["CRITICAL".into(), "GPU 93°".into()],
+    );
+    sink.show(&frame).unwrap();
+    sink.show(&frame.clone().on_screen(1)).unwrap();
+    let posted = mock.wait_for("game_event", 2);
+    assert_eq!(posted[0]["event"], "ALERT");
+    assert_eq!(posted[0]["data"]["frame"]["line2"], "GPU 93°");
+    assert_eq!(posted[1]["event"], "SCREEN2_ALERT");
+}
+
+#[test]
+fn alert_effects_color_the_zone_and_vibrate_when_raised() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+    sink.bind_alert_effects(AlertEffects {
+        color_zone: Zone {
+            device_type: String::from("mouse"),
+            zone: String::from("logo"),
+        },
+        warn_color: None,
+        crit_color: Rgb::parse("255,0,0"),
+        flash: 2.0,
+        tactile_zone: Zone {
+            device_type: String::from("tactile"),
+            zone: String::from("one"),
+        },
+        warn_tactile: None,
+        crit_tactile: Some(String::from("ti_predefined_strongclick_100")),
+    })
+    .unwrap();
+
+    let binds = mock.requests_to("bind_game_event");
+    let color = binds
+        .iter()
+        .find(|bind| bind["event"] == ALERT_COLOR_EVENT)
+        .unwrap();
+    assert_eq!(color["max_value"], 2);
+    let handler = &color["handlers"][0];
+    assert_eq!(handler["device-type"], "mouse");
+    assert_eq!(handler["zone"], "logo");
+    assert_eq!(handler["mode"], "color");
+    assert_eq!(
+        handler["color"],
+        json!([{"low": 2, "high": 2, "color": {"red": 255, "green": 0, "blue": 0}}])
+    );
+    assert_eq!(handler["rate"]["frequency"], 2.0);
+    let tactile = binds
+        .iter()
+        .find(|bind| bind["event"] == "ALERT_CRITICAL")
+        .unwrap();
+    assert_eq!(tactile["handlers"][0]["mode"], "vibrate");
+    assert_eq!(
+        tactile["handlers"][0]["pattern"][0]["type"],
+        "ti_predefined_strongclick_100"
+    );
+    assert!(!binds.iter().any(|bind| bind["event"] == "ALERT_WARNING"));
+
+    for severity in [
+        None,
+        Some(Severity::Critical),
+        Some(Severity::Critical),
+        None,
+    ] {
+        sink.alert(severity).unwrap();
+    }
+    let posted = mock
+        .wait_for("game_event", 5)
+        .iter()
+        .map(|event| (event["event"].clone(), event["data"]["value"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        posted,
+        vec![
+            (json!("ALERT_COLOR"), json!(0)),
+            (json!("ALERT_COLOR"), json!(2)),
+            (json!("ALERT_CRITICAL"), json!(0)),
+            (json!("ALERT_COLOR"), json!(2)),
+            (json!("ALERT_COLOR"), json!(0)),
+        ]
+    );
+}
+
+#[test]
+fn gradients_map_the_reading_to_a_color_value() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+    let gradient = ColorGradient {
+        zone: Zone {
+            device_type: String::from("rgb-per-key-zones"),
+            zone: String::from("function-keys"),
+        },
+        zero: Rgb::parse("0,255,0").unwrap(),
+        hundred: Rgb::parse("255,0,0").unwrap(),
+    };
+    sink.bind_gradient(3, &gradient).unwrap();
+
+    let bind = mock
+        .requests_to("bind_game_event")
+        .into_iter()
+        .find(|bind| bind["event"] == "LIGHTING3")
+        .unwrap();
+    assert_eq!(bind["min_value"], 0);
+    assert_eq!(bind["max_value"], 100);
+    let handler = &bind["handlers"][0];
+    assert_eq!(handler["zone"], "function-keys");
+    assert_eq!(
+        handler["color"]["gradient"],
+        json!({
+            "zero": {"red": 0, "green": 255, "blue": 0},
+            "hundred": {"red": 255, "green": 0, "blue": 0},
+        })
+    );
+
+    sink.gradient(3, 0.456).unwrap();
+    sink.gradient(3, 1.5).unwrap();
+    let posted = mock.wait_for("game_event", 2);
+    assert_eq!(posted[0]["event"], "LIGHTING3");
+    assert_eq!(posted[0]["data"]["value"], 46);
+    assert_eq!(posted[1]["data"]["value"], 100);
+}
+
+#[test]
+fn frames_are_dropped_while_gg_is_gone() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+
+    mock.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let frame = Frame::page(1, vec!["a".into(), "b".into(), "c".into()]);
+    assert!(sink.show(&frame).is_ok());
+    assert!(!sink.is_connected());
+}
+
+#[test]
+fn reconnects_and_rebinds_after_gg_restart() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let first = MockGameSense::start();
+    let mut sink = sink(&first, 2);
+    let frame = Frame::page(1, vec!["a".into(), "b".into(), "c".into()]);
+    sink.show(&frame).unwrap();
+    assert_eq!(first.requests_to("game_event").len(), 1);
+
+    // GG restarts on a new port and rewrites coreProps.json
+    first.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let second = MockGameSense::start_at(first.program_data.clone());
+
+    sink.show(&frame).unwrap();
+    assert!(sink.is_connected());
+    assert_eq!(second.requests_to("game_metadata").len(), 1);
+    let events = second
+        .requests_to("bind_game_event")
+        .iter()
+        .map(|bind| bind["event"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(events, vec!["PAGE1", "PAGE2", "ERROR", "ALERT"]);
+    let posted = second.requests_to("game_event");
+    assert_eq!(posted.len(), 1);
+    assert_eq!(posted[0]["data"]["frame"], frame.to_value());
+}
+
+#[test]
+fn backs_off_while_gg_is_down() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut client = SteelSeriesClient::new(metadata(), mock.core_props());
+    client.connect().unwrap();
+    mock.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    client.trigger_event("PAGE1", 0).unwrap();
+    assert!(!client.is_connected());
+
+    // The next attempt is scheduled a second out, so an immediate retry doesn't hit GG
+    let second = MockGameSense::start_at(mock.program_data.clone());
+    client.trigger_event("PAGE1", 1).unwrap();
+    assert!(!client.is_connected());
+    assert!(second.requests().is_empty());
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    client.trigger_event("PAGE1", 2).unwrap();
+    assert!(client.is_connected());
+    assert_eq!(second.requests_to("game_event").len(), 1);
+}
+
+#[test]
+fn bindings_made_while_gg_is_down_are_replayed() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut client = SteelSeriesClient::new(metadata(), mock.core_props());
+    client.connect().unwrap();
+    mock.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    let handlers = vec![json!({"device-type": "screened", "mode": "screen"})];
+    assert!(client
+        .bind_event("PAGE1", None, None, None, None, handlers)
+        .is_ok());
+    assert!(!client.is_connected());
+
+    let second = MockGameSense::start_at(mock.program_data.clone());
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    client.trigger_event("PAGE1", 0).unwrap();
+    assert!(client.is_connected());
+    let binds = second.requests_to("bind_game_event");
+    assert_eq!(binds.len(), 1);
+    assert_eq!(binds[0]["event"], "PAGE1");
+    assert_eq!(second.requests_to("game_event").len(), 1);
+}
+
+#[test]
+fn rejected_bindings_are_returned_and_not_replayed() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    mock.reject("BAD");
+    let mut client = SteelSeriesClient::new(metadata(), mock.core_props());
+    client.connect().unwrap();
+
+    let handlers = || vec![json!({"device-type": "screened", "mode": "screen"})];
+    client
+        .bind_event("GOOD", None, None, None, None, handlers())
+        .unwrap();
+    assert!(client
+        .bind_event("BAD", None, None, None, None, handlers())
+        .is_err());
+    assert!(client.trigger_event("BAD", 0).is_err());
+    // GG is still there, only the request was refused
+    assert!(client.is_connected());
+
+    mock.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let second = MockGameSense::start_at(mock.program_data.clone());
+    client.trigger_event("GOOD", 0).unwrap();
+    assert!(client.is_connected());
+    let events = second
+        .requests_to("bind_game_event")
+        .iter()
+        .map(|bind| bind["event"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(events, vec!["GOOD"]);
+    assert_eq!(second.requests_to("game_event").len(), 1);
+}
+
+#[test]
+fn shutdown_sends_goodbye_and_removes_game() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+    sink.show(&Frame::page(1, vec!["a".into(), "b".into(), "c".into()]))
+        .unwrap();
+    let removed_on_connect = mock.requests_to("remove_game").len();
+
+    sink.shutdown().unwrap();
+
+    let requests = mock.requests();
+    let last = &requests[requests.len() - 1];
+    assert_eq!(last.endpoint, "remove_game");
+    assert_eq!(last.body["game"], "HWINFO");
+    assert_eq!(
+        mock.requests_to("remove_game").len(),
+        removed_on_connect + 1
+    );
+    let goodbye = &requests[requests.len() - 2];
+    assert_eq!(goodbye.endpoint, "game_event");
+    assert_eq!(goodbye.body["event"], "ERROR");
+    assert_eq!(goodbye.body["data"]["frame"]["line2"], "Closed");
+    assert!(!sink.is_connected());
+}
+
+#[test]
+fn shutdown_leaves_gg_alone_while_disconnected() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let mut sink = sink(&mock, 1);
+    mock.stop();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    sink.show(&Frame::page(1, vec!["a".into(), "b".into(), "c".into()]))
+        .unwrap();
+    assert!(!sink.is_connected());
+
+    let second = MockGameSense::start_at(mock.program_data.clone());
+    sink.shutdown().unwrap();
+    assert!(second.requests().is_empty());
+}
+
+#[test]
+fn custom_game_metadata_is_registered() {
+    let _lock = PROGRAM_DATA.lock().unwrap();
+    let mock = MockGameSense::start();
+    let metadata = GameMetadata {
+        game: String::from("HWINFO_TEAM-2"),
+        game_display_name: String::from("Team Stats"),
+        developer: String::from("Our Team"),
+        icon_color_id: Some(5),
+        deinitialize_timer_length_ms: Some(30000),
+    };
+    metadata.validate().unwrap();
+    let mut client = SteelSeriesClient::new(metadata, mock.core_props());
+    client.connect().unwrap();
+
+    let registered = &mock.requests_to("game_metadata")[0];
+    assert_eq!(registered["game"], "HWINFO_TEAM-2");
+    assert_eq!(registered["game_display_name"], "Team Stats");
+    assert_eq!(registered["developer"], "Our Team");
+    assert_eq!(registered["icon_color_id"], 5);
+    assert_eq!(registered["deinitialize_timer_length_ms"], 30000);
+}
+
+#[test]
+fn invalid_game_metadata_is_rejected() {
+    for game in ["", "hwinfo", "HW INFO", "HWINFO!"] {
+        let mut invalid = metadata();
+        invalid.game = String::from(game);
+        assert!(invalid.validate().is_err(), "{:?} should be rejected", game);
+    }
+    let mut invalid = metadata();
+    invalid.icon_color_id = Some(12);
+    assert!(invalid.validate().is_err());
+    let mut invalid = metadata();
+    invalid.deinitialize_timer_length_ms = Some(500);
+    assert!(invalid.validate().is_err());
+}
@@ -0,0 +1,81 @@
+//! Reads fake HWiNFO shared memory, laid out the way HWiNFO shares it, through
+//! `Hwinfo::read`.
+
+use hwinfo_steelseries_oled::counter::Counter;
+use hwinfo_steelseries_oled::utils::{lighting_fraction, track_counters};
+use hwinfo_steelseries_oled::Hwinfo;
+use ini::Ini;
+
+const NET: &str = "Network: Intel Ethernet Controller I225-V";
+const HEADER_SIZE: usize = 44;
+const READING_SIZE: usize = 460;
+
+/// Shared memory holding one `Total DL` reading of the first sensor, polled at `poll_time`.
+fn shared_memory(poll_time: i64, total_dl: f64) -> Vec<u8> {
+    let mut memory = vec![0u8; HEADER_SIZE + READING_SIZE];
+    memory[12..20].copy_from_slice(&poll_time.to_le_bytes());
+    memory[32..36].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    memory[36..40].copy_from_slice(&(READING_SIZE as u32).to_le_bytes());
+    memory[40..44].copy_from_slice(&1u32.to_le_bytes());
+
+    let reading = &mut memory[HEADER_SIZE..];
+    for label in [12, 140, 316] {
+        reading[label..label + 8].copy_from_slice(b"Total DL");
+    }
+    reading[268..270].copy_from_slice(b"MB");
+    reading[444..446].copy_from_slice(b"MB");
+    for value in [284, 292, 300, 308] {
+        reading[value..value + 8].copy_from_slice(&total_dl.to_le_bytes());
+    }
+    memory
+}
+
+#[test]
+fn reads_the_readings() {
+    let mut hwinfo = Hwinfo::from_readings(&[(NET, "Total DL", 0.0, "MB")]);
+    hwinfo.read(&shared_memory(1000, 123.5)).unwrap();
+    let reading = hwinfo.get(NET, "Total DL").unwrap();
+    assert_eq!({ reading.value }, 123.5);
+    assert_eq!(reading.unit(), "MB");
+}
+
+#[test]
+fn rates_of_tracked_counters_across_polls() {
+    let counter = Counter::default();
+    let mut hwinfo = Hwinfo::from_readings(&[(NET, "Total DL", 0.0, "MB")]);
+    hwinfo.track_counter(NET, "Total DL");
+
+    hwinfo.read(&shared_memory(1000, 100.0)).unwrap();
+    assert_eq!(hwinfo.rate(NET, "Total DL", &counter), None);
+
+    hwinfo.read(&shared_memory(1002, 150.0)).unwrap();
+    assert_eq!(hwinfo.rate(NET, "Total DL", &counter), Some(25.0));
+
+    // Pulling again before HWiNFO polls keeps the rate
+    hwinfo.read(&shared_memory(1002, 150.0)).unwrap();
+    assert_eq!(hwinfo.rate(NET, "Total DL", &counter), Some(25.0));
+}
+
+#[test]
+fn rejects_truncated_memory() {
+    let mut hwinfo = Hwinfo::from_readings(&[(NET, "Total DL", 0.0, "MB")]);
+    assert!(hwinfo.read(&[0u8; 20]).is_err());
+    let memory = shared_memory(1000, 100.0);
+    assert!(hwinfo.read(&memory[..HEADER_SIZE + 100]).is_err());
+}
+
+#[test]
+fn lighting_rates_are_tracked() {
+    let config = Ini::load_from_str(&format!(
+        "[Lighting]\nsensor_0=\"{};Total DL\"\nrate_0=true\nmax_0=50\n",
+        NET
+    ))
+    .unwrap();
+    let lighting = config.section(Some("Lighting")).unwrap();
+    let mut hwinfo = Hwinfo::from_readings(&[(NET, "Total DL", 0.0, "MB")]);
+    track_counters(&[lighting], &mut hwinfo).unwrap();
+
+    hwinfo.read(&shared_memory(1000, 100.0)).unwrap();
+    hwinfo.read(&shared_memory(1002, 150.0)).unwrap();
+    assert_eq!(lighting_fraction(lighting, 0, &hwinfo).unwrap(), Some(0.5));
+}
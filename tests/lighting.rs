@@ -0,0 +1,26 @@
+use hwinfo_steelseries_oled::lighting::Rgb;
+
+#[test]
+fn colors_parse_from_channels_or_hex() {
+    let red = Rgb {
+        red: 255,
+        green: 0,
+        blue: 0,
+    };
+    assert_eq!(Rgb::parse("255,0,0"), Some(red));
+    assert_eq!(Rgb::parse(" 255, 0 ,0 "), Some(red));
+    assert_eq!(Rgb::parse("#FF0000"), Some(red));
+    assert_eq!(
+        Rgb::parse("#20a0ff"),
+        Some(Rgb {
+            red: 0x20,
+            green: 0xa0,
+            blue: 0xff,
+        })
+    );
+    for invalid in [
+        "", "255,0", "256,0,0", "red", "#ff00", "#ff00000", "#gg0000",
+    ] {
+        assert_eq!(Rgb::parse(invalid), None, "{:?}", invalid);
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use hwinfo_steelseries_oled::log_file::{csv_row, RotatingFile};
+
+/// Empty directory for one test's log files.
+fn log_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hwinfo-log-file-{}", test));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn read(path: PathBuf) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn header_is_written_once() {
+    let path = log_dir("header").join("history.csv");
+    let mut file = RotatingFile::new(path.clone(), 1024, None, 2);
+    file.append(Some("timestamp,cpu"), "1,40").unwrap();
+    file.append(Some("timestamp,cpu"), "2,41").unwrap();
+    assert_eq!(read(path.clone()), "timestamp,cpu\n1,40\n2,41\n");
+
+    // A later run carries on with the same columns
+    let mut file = RotatingFile::new(path.clone(), 1024, None, 2);
+    file.append(Some("timestamp,cpu"), "3,42").unwrap();
+    assert_eq!(read(path.clone()), "timestamp,cpu\n1,40\n2,41\n3,42\n");
+    assert!(!file.rotated(1).exists());
+}
+
+#[test]
+fn new_columns_start_a_new_file() {
+    let path = log_dir("columns").join("history.csv");
+    let mut file = RotatingFile::new(path.clone(), 1024, None, 2);
+    file.append(Some("timestamp,cpu"), "1,40").unwrap();
+
+    let mut file = RotatingFile::new(path.clone(), 1024, None, 2);
+    file.append(Some("timestamp,cpu,gpu"), "2,41,60").unwrap();
+    assert_eq!(read(path.clone()), "timestamp,cpu,gpu\n2,41,60\n");
+    assert_eq!(read(file.rotated(1)), "timestamp,cpu\n1,40\n");
+}
+
+#[test]
+fn rotates_by_size() {
+    let path = log_dir("size").join("history.jsonl");
+    let mut file = RotatingFile::new(path.clone(), 10, None, 2);
+    for row in ["first row", "second row", "third row", "fourth row"] {
+        file.append(None, row).unwrap();
+    }
+    assert_eq!(read(path.clone()), "fourth row\n");
+    assert_eq!(read(file.rotated(1)), "third row\n");
+    assert_eq!(read(file.rotated(2)), "second row\n");
+    // Only `keep` old files
+    assert!(!file.rotated(3).exists());
+}
+
+#[test]
+fn rotates_by_age() {
+    let path = log_dir("age").join("history.csv");
+    let hour = Duration::from_secs(3600);
+    let mut file = RotatingFile::new(path.clone(), 1024 * 1024, Some(hour), 1);
+    let start = SystemTime::now();
+    file.append_at(start, Some("timestamp"), "1").unwrap();
+    file.append_at(start + hour / 2, Some("timestamp"), "2").unwrap();
+    assert!(!file.rotated(1).exists());
+
+    file.append_at(start + hour, Some("timestamp"), "3").unwrap();
+    assert_eq!(read(file.rotated(1)), "timestamp\n1\n2\n");
+    assert_eq!(read(path.clone()), "timestamp\n3\n");
+}
+
+#[test]
+fn keep_zero_drops_old_rows() {
+    let path = log_dir("keep").join("history.jsonl");
+    let mut file = RotatingFile::new(path.clone(), 5, None, 0);
+    file.append(None, "first").unwrap();
+    file.append(None, "second").unwrap();
+    assert_eq!(read(path.clone()), "second\n");
+    assert!(!file.rotated(1).exists());
+}
+
+#[test]
+fn csv_quotes_fields() {
+    let fields = ["GPU".to_string(), "a,b".to_string(), "say \"hi\"".to_string()];
+    assert_eq!(csv_row(&fields), "GPU,\"a,b\",\"say \"\"hi\"\"\"");
+}
@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use hwinfo_steelseries_oled::mqtt_client::{
+    connect_packet, packet, publish_packet, MqttClient, MqttLogin, DISCONNECT,
+};
+
+const LOGIN: MqttLogin = MqttLogin {
+    client_id: "hwinfo",
+    username: None,
+    password: None,
+    keep_alive: 60,
+};
+
+/// Length bytes of a packet with a body of `len` bytes.
+fn remaining_length(len: usize) -> Vec<u8> {
+    let packet = packet(0x30, &vec![0; len]);
+    assert_eq!(packet[0], 0x30);
+    packet[1..packet.len() - len].to_vec()
+}
+
+/// Reads one packet, fixed header included.
+fn read_packet(stream: &mut impl Read) -> Vec<u8> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header).unwrap();
+    let mut packet = header.to_vec();
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        packet.push(byte[0]);
+        len |= ((byte[0] & 0x7F) as usize) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).unwrap();
+    packet.extend_from_slice(&body);
+    packet
+}
+
+#[test]
+fn remaining_length_boundaries() {
+    assert_eq!(remaining_length(0), vec![0x00]);
+    assert_eq!(remaining_length(127), vec![0x7F]);
+    assert_eq!(remaining_length(128), vec![0x80, 0x01]);
+    assert_eq!(remaining_length(16383), vec![0xFF, 0x7F]);
+    assert_eq!(remaining_length(16384), vec![0x80, 0x80, 0x01]);
+}
+
+#[test]
+fn publish_frames_topic_and_payload() {
+    assert_eq!(
+        publish_packet("hw/cpu", b"42.000", false).unwrap(),
+        b"\x30\x0E\x00\x06hw/cpu42.000".to_vec()
+    );
+    // Retained, for discovery configs
+    assert_eq!(publish_packet("t", b"", true).unwrap(), b"\x31\x03\x00\x01t".to_vec());
+
+    let long_payload = vec![b'x'; 200];
+    let packet = publish_packet("t", &long_payload, false).unwrap();
+    assert_eq!(packet[..5], [0x30, 0xCB, 0x01, 0x00, 0x01]);
+    assert_eq!(packet.len(), 5 + 1 + 200);
+}
+
+#[test]
+fn connect_sets_login_flags() {
+    assert_eq!(
+        connect_packet(&LOGIN),
+        b"\x10\x12\x00\x04MQTT\x04\x02\x00\x3C\x00\x06hwinfo".to_vec()
+    );
+    let login = MqttLogin {
+        username: Some("user"),
+        password: Some("pw"),
+        ..LOGIN
+    };
+    let packet = connect_packet(&login);
+    assert_eq!(packet[9], 0xC2);
+    assert!(packet.ends_with(b"\x00\x06hwinfo\x00\x04user\x00\x02pw"));
+}
+
+#[test]
+fn connects_then_publishes_to_a_broker() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let broker = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let connect = read_packet(&mut stream);
+        stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+        let publish = read_packet(&mut stream);
+        let disconnect = read_packet(&mut stream);
+        (connect, publish, disconnect)
+    });
+
+    let mut client = MqttClient::connect("127.0.0.1", port, &LOGIN).unwrap();
+    client.publish("hw/cpu", b"42.000", false).unwrap();
+    client.disconnect();
+
+    let (connect, publish, disconnect) = broker.join().unwrap();
+    assert_eq!(connect, connect_packet(&LOGIN));
+    assert_eq!(publish, publish_packet("hw/cpu", b"42.000", false).unwrap());
+    assert_eq!(disconnect, DISCONNECT.to_vec());
+}
+
+#[test]
+fn refused_connection_is_an_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let broker = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_packet(&mut stream);
+        // Not authorized
+        stream.write_all(&[0x20, 0x02, 0x00, 0x05]).unwrap();
+    });
+    let result = MqttClient::connect("127.0.0.1", port, &LOGIN);
+    broker.join().unwrap();
+    assert!(result.is_err());
+}
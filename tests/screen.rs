@@ -0,0 +1,87 @@
+//! Renders a screen configured by an ini file, from fake HWiNFO readings, through the
+//! GameSense output to the mock GG server in `tests/common`.
+
+mod common;
+
+use common::MockGameSense;
+use hwinfo_steelseries_oled::client::{core_props_path, GameMetadata, SteelSeriesClient};
+use hwinfo_steelseries_oled::display::{DisplaySink, GameSenseSink};
+use hwinfo_steelseries_oled::screen::Screen;
+use hwinfo_steelseries_oled::Hwinfo;
+use ini::Ini;
+
+const CPU: &str = "CPU [#0]: AMD Ryzen 7 5800X";
+const GPU: &str = "GPU [#0]: NVIDIA GeForce RTX 3080";
+const SYSTEM: &str = "System: ASUS ";
+
+fn config() -> Ini {
+    Ini::load_from_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/screen.ini"
+    ))
+    .unwrap()
+}
+
+fn readings() -> Hwinfo {
+    Hwinfo::from_readings(&[
+        (CPU, "CPU (Tctl/Tdie)", 45.0, "°C"),
+        (CPU, "Core Clocks (avg)", 4123.0, "MHz"),
+        (GPU, "GPU Temperature", 61.0, "°C"),
+        (SYSTEM, "Physical Memory Load", 23.4, "%"),
+    ])
+}
+
+#[test]
+fn renders_the_configured_page_to_gamesense() {
+    let mock = MockGameSense::start();
+    mock.install();
+    let config = config();
+    let mut screens = Screen::from_ini(&config).unwrap();
+    let screen = &mut screens[0];
+
+    let metadata = GameMetadata {
+        game: String::from("HWINFO"),
+        game_display_name: String::from("HWiNFO_Stats"),
+        developer: String::from("Ryan"),
+        icon_color_id: None,
+        deinitialize_timer_length_ms: None,
+    };
+    let mut client = SteelSeriesClient::new(metadata, core_props_path().unwrap());
+    client.connect().unwrap();
+    let mut sink = GameSenseSink::new(client, &[screen.layout()]).unwrap();
+
+    let hwinfo = readings();
+    screen.sample(&hwinfo);
+    sink.show(&screen.frame(&hwinfo).unwrap()).unwrap();
+
+    let posted = mock.wait_for("game_event", 1);
+    assert_eq!(posted[0]["event"], "PAGE1");
+    assert_eq!(posted[0]["data"]["value"], 0);
+    let frame = &posted[0]["data"]["frame"];
+    assert_eq!(frame["line1"], "CPU 45°     GPU 61°");
+    assert_eq!(frame["line2"], "RAM 23%");
+    assert_eq!(frame["line3"], "CLK 4.1G");
+}
+
+#[test]
+fn rejects_more_sensors_per_line_than_sensors() {
+    let config = Ini::load_from_str(
+        "[Main]\nstyle=custom\ndevice=screened-128x48\nsensors_per_line=200\n\n[PAGE1.Sensors]\nrender=bitmap\n",
+    )
+    .unwrap();
+    assert!(Screen::from_ini(&config).is_err());
+}
+
+#[test]
+fn bitmap_pages_need_a_sized_device() {
+    let page = "[PAGE1.Sensors]\nrender=bitmap\n";
+    let config = Ini::load_from_str(&format!("[Main]\nstyle=custom\n\n{}", page)).unwrap();
+    assert!(Screen::from_ini(&config).is_err());
+
+    let config = Ini::load_from_str(&format!(
+        "[Main]\nstyle=custom\ndevice=screened-128x48\n\n{}",
+        page
+    ))
+    .unwrap();
+    assert_eq!(Screen::from_ini(&config).unwrap()[0].device.height, 48);
+}
@@ -0,0 +1,202 @@
+//! Width model and line fitting for GG's text lines, with worst-case readings.
+
+use hwinfo_steelseries_oled::text::{
+    abbreviations, char_width, columns, fit, grid, marquee, pad, text_width, truncate, Align, Cell,
+    MARQUEE_GAP, SPACE_WIDTH,
+};
+
+const SCREEN_WIDTH: usize = 128;
+
+#[test]
+fn measures_proportional_text() {
+    assert_eq!(char_width(' '), SPACE_WIDTH);
+    assert!(char_width('i') < char_width('a'));
+    assert!(char_width('a') < char_width('%'));
+    assert!(char_width('▲') < char_width('💻'));
+    assert_eq!(text_width("CPU"), 21);
+    assert_eq!(text_width(""), 0);
+}
+
+#[test]
+fn truncates_to_whole_characters() {
+    assert_eq!(truncate("GPU Temperature", 28), "GPU ");
+    assert_eq!(truncate("ok", 100), "ok");
+    assert!(text_width(&truncate("💻💻💻💻💻💻💻💻💻💻💻💻", SCREEN_WIDTH)) <= SCREEN_WIDTH);
+}
+
+#[test]
+fn pads_to_alignment() {
+    assert_eq!(pad("45", 22, Align::Left), "45  ");
+    assert_eq!(pad("45", 22, Align::Right), "  45");
+    assert_eq!(pad("45", 30, Align::Center), "  45  ");
+    assert_eq!(pad("too wide", 4, Align::Right), "too wide");
+}
+
+#[test]
+fn abbreviates_numbers_before_truncating() {
+    assert_eq!(abbreviations("45.7°"), vec!["46°"]);
+    assert_eq!(abbreviations("4123MHz"), vec!["4.1GHz"]);
+    assert_eq!(abbreviations("1234.6k/s"), vec!["1235k/s", "1.2M/s"]);
+    assert_eq!(abbreviations("2500000"), vec!["2.5M"]);
+    assert_eq!(abbreviations("1500K"), vec!["1.5M"]);
+    assert!(abbreviations("1234RPM").is_empty());
+    assert!(abbreviations("1500mW").is_empty());
+    assert!(abbreviations("1500ms").is_empty());
+    assert!(abbreviations("CPU").is_empty());
+
+    assert_eq!(fit("100.0%", 30), "100%");
+    assert_eq!(fit("4123MHz", 45), "4.1GHz");
+    assert_eq!(fit("Ryzen", 20), "Ry");
+}
+
+#[test]
+fn columns_stay_aligned_across_lines() {
+    let lines = [
+        vec!["CPU".to_string(), "GPU".to_string(), "MEM".to_string()],
+        vec!["5°".to_string(), "45°".to_string(), "8G".to_string()],
+        vec!["100%".to_string(), "99%".to_string(), "63G".to_string()],
+    ];
+    let laid_out = lines
+        .iter()
+        .map(|cells| columns(cells, SCREEN_WIDTH, Align::Left))
+        .collect::<Vec<String>>();
+
+    // Each column starts within a space of the column boundary on every line
+    for line in laid_out.iter() {
+        let cells = line.split_whitespace().collect::<Vec<&str>>();
+        assert_eq!(cells.len(), 3);
+        let second = line.find(cells[1]).unwrap();
+        let start = text_width(&line[..second]);
+        assert!(start <= SCREEN_WIDTH / 3 && start + SPACE_WIDTH > SCREEN_WIDTH / 3);
+        assert!(text_width(line) <= SCREEN_WIDTH);
+    }
+}
+
+#[test]
+fn worst_case_readings_fit_the_screen() {
+    let worst = [
+        vec![
+            "💻 100.0°".to_string(),
+            "100.0%".to_string(),
+            "999.9W".to_string(),
+        ],
+        vec![
+            "⏰ 12:59pm".to_string(),
+            "4999MHz".to_string(),
+            "4999MHz".to_string(),
+        ],
+        vec![
+            "NET ▲ 123456.7k/s".to_string(),
+            "NET ▼ 99999.9k/s".to_string(),
+        ],
+    ];
+    for cells in worst.iter() {
+        for align in [Align::Left, Align::Right, Align::Center] {
+            let line = columns(cells, SCREEN_WIDTH, align);
+            assert!(
+                text_width(&line) <= SCREEN_WIDTH,
+                "{:?} is {} px",
+                line,
+                text_width(&line)
+            );
+        }
+    }
+    assert!(text_width(&fit("GPU [#0]: NVIDIA GeForce RTX 3090", SCREEN_WIDTH)) <= SCREEN_WIDTH);
+}
+
+fn cell(text: &str, width: Option<usize>, align: Align) -> Cell {
+    Cell {
+        text: text.to_string(),
+        width,
+        align,
+    }
+}
+
+/// Pixel offset where `needle` starts in `line`.
+fn offset(line: &str, needle: &str) -> usize {
+    text_width(&line[..line.find(needle).unwrap()])
+}
+
+#[test]
+fn grid_aligns_mixed_width_labels() {
+    let up = grid(
+        &[
+            cell("NET ▲", Some(48), Align::Left),
+            cell("1.2k/s", None, Align::Right),
+        ],
+        " ",
+        SCREEN_WIDTH,
+    );
+    let down = grid(
+        &[
+            cell("DL", Some(48), Align::Left),
+            cell("99.5k/s", None, Align::Right),
+        ],
+        " ",
+        SCREEN_WIDTH,
+    );
+
+    // Right aligned values end within a space of the screen edge on both lines
+    for line in [&up, &down] {
+        assert!(text_width(line) <= SCREEN_WIDTH);
+        assert!(text_width(line) + SPACE_WIDTH > SCREEN_WIDTH);
+    }
+    assert!(offset(&up, "1.2k/s") > 48);
+    assert!(offset(&down, "99.5k/s") > 48);
+}
+
+#[test]
+fn grid_supports_any_number_of_cells_and_separators() {
+    let cells = (0..5)
+        .map(|i| cell(&i.to_string(), None, Align::Center))
+        .collect::<Vec<Cell>>();
+    let line = grid(&cells, "|", SCREEN_WIDTH);
+
+    assert_eq!(line.matches('|').count(), 4);
+    assert!(text_width(&line) <= SCREEN_WIDTH);
+    // Centered in ~22 px columns
+    let first = offset(&line, "0");
+    assert!((6..=12).contains(&first), "{:?}", line);
+}
+
+#[test]
+fn grid_fits_cells_to_their_width() {
+    let line = grid(
+        &[
+            cell("GPU [#0]: NVIDIA GeForce RTX 3090", Some(44), Align::Left),
+            cell("4123MHz", Some(46), Align::Right),
+            cell("100.0%", None, Align::Right),
+        ],
+        " ",
+        SCREEN_WIDTH,
+    );
+
+    assert!(line.starts_with("GPU [#"));
+    assert!(line.contains("4.1GHz"));
+    assert!(line.ends_with("100%"));
+    assert!(text_width(&line) <= SCREEN_WIDTH);
+}
+
+#[test]
+fn marquee_leaves_short_text_alone() {
+    assert_eq!(marquee("CPU 45°", 7, SCREEN_WIDTH), "CPU 45°");
+}
+
+#[test]
+fn marquee_scrolls_and_wraps_around() {
+    let text = "Now playing: Daft Punk - Harder, Better, Faster, Stronger";
+    let length = text.chars().count() + MARQUEE_GAP.chars().count();
+
+    let first = marquee(text, 0, SCREEN_WIDTH);
+    let second = marquee(text, 2, SCREEN_WIDTH);
+    assert!(text.starts_with(&first));
+    assert!(text[2..].starts_with(&second));
+    for offset in 0..length {
+        assert!(text_width(&marquee(text, offset, SCREEN_WIDTH)) <= SCREEN_WIDTH);
+    }
+
+    // Past the end the start comes round again after the gap
+    let wrapped = marquee(text, length - MARQUEE_GAP.len() - 2, SCREEN_WIDTH);
+    assert!(wrapped.starts_with(&format!("er{}Now", MARQUEE_GAP)));
+    assert_eq!(marquee(text, length, SCREEN_WIDTH), first);
+}
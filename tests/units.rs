@@ -0,0 +1,86 @@
+use hwinfo_steelseries_oled::units::{autoscale, Conversion, Quantity, Unit};
+
+fn convert(conversion: &str, value: f64) -> f64 {
+    Conversion::parse(conversion).unwrap().apply(value, 100.0)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn temperatures() {
+    assert_close(convert("C/F", 100.0), 212.0);
+    assert_close(convert("°C/°F", -40.0), -40.0);
+    assert_close(convert("F/C", 32.0), 0.0);
+    assert_close(convert("C/K", 0.0), 273.15);
+}
+
+#[test]
+fn sizes_rates_frequencies_and_power() {
+    assert_close(convert("MB/GB", 16384.0), 16.0);
+    assert_close(convert("GB/TB", 512.0), 0.5);
+    assert_close(convert("GB/MB", 2.0), 2048.0);
+    assert_close(convert("KB/s/MB/s", 2048.0), 2.0);
+    assert_close(convert("MB/s/Mbit/s", 1.0), 8.388608);
+    assert_close(convert("MHz/GHz", 4350.0), 4.35);
+    assert_close(convert("W/kW", 750.0), 0.75);
+}
+
+#[test]
+fn fan_speed_as_share_of_max() {
+    let conversion = Conversion::parse("RPM/%").unwrap();
+    assert!(conversion.is_relative());
+    assert_close(conversion.apply(900.0, 1800.0), 50.0);
+    let back = Conversion::parse("%/RPM").unwrap();
+    assert_close(back.apply(25.0, 1800.0), 450.0);
+    assert!(!Conversion::parse("C/F").unwrap().is_relative());
+}
+
+#[test]
+fn units_are_found_by_name_or_ignoring_case() {
+    assert_eq!(Unit::from_name("mw").unwrap().name, "mW");
+    assert_eq!(Unit::from_name("mbit/s").unwrap().name, "Mbit/s");
+    assert_eq!(Unit::from_name("°F").unwrap().suffix, "°F");
+    assert_eq!(Unit::from_name("GB").unwrap().quantity, Quantity::Data);
+    assert!(Unit::from_name("parsec").is_none());
+}
+
+#[test]
+fn bad_conversions_are_errors() {
+    assert!(Conversion::parse("MB/F")
+        .unwrap_err()
+        .to_string()
+        .contains("Can't convert MB to F"));
+    assert!(Conversion::parse("MB/GiB")
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown conversion"));
+    assert!(Conversion::parse("MB").is_err());
+}
+
+#[test]
+fn autoscale_keeps_numbers_within_width() {
+    let kbs = Unit::from_name("KB/s").unwrap();
+    let scale = |value: f64, width: usize| {
+        let (number, suffix) = autoscale(value, kbs, width).unwrap();
+        format!("{}{}", number, suffix)
+    };
+    assert_eq!(scale(1.2, 3), "1.2K/s");
+    assert_eq!(scale(0.5, 3), "512B/s");
+    assert_eq!(scale(850.0, 3), "850K/s");
+    assert_eq!(scale(1000.0, 3), "1.0M/s");
+    assert_eq!(scale(13_000.0, 3), "13M/s");
+    assert_eq!(scale(12_800.0, 4), "12.5M/s");
+    assert_eq!(scale(10_220.0, 3), "10M/s");
+    assert_eq!(scale(0.0, 3), "0.0B/s");
+
+    let mb = Unit::from_name("MB").unwrap();
+    assert_eq!(autoscale(16_107.0, mb, 4).unwrap(), ("15.7".into(), "G"));
+    assert!(autoscale(70.0, Unit::from_name("C").unwrap(), 3).is_none());
+}